@@ -1,15 +1,24 @@
-import init, {GameEvent, GameState, GameWasm} from "snake-wasm"
+import init, {Action, GameEvent, GameEventInfo, GameWasm} from "snake-wasm"
 import AudioManger from "./AudioManger.ts";
 
+type EventListener = (event: GameEventInfo) => void
+
+const HIGH_SCORES_KEY = 'snake-high-scores'
+const STATS_KEY = 'snake-stats'
+const ACHIEVEMENTS_KEY = 'snake-achievements'
+const KEY_BINDINGS_KEY = 'snake-keys'
+// Standard gamepad layout indices of the left and right shoulder buttons
+const GAMEPAD_TURN_BUTTONS: [number, Action][] = [[4, Action.TurnLeft], [5, Action.TurnRight]]
+
 class Game {
-    private readonly width: number
-    private readonly height: number
+    private width: number
+    private height: number
 
+    private readonly canvas: HTMLCanvasElement
     private readonly ctx: CanvasRenderingContext2D | null
 
-    private started: boolean = false
-
     private showPerformanceInfo: boolean = false
+    private smoothMovement: boolean = false
     private wasm: any
     private wasmGame: GameWasm | null = null
     private imgData: ImageData | null | undefined
@@ -20,14 +29,24 @@ class Game {
     private lastFpsUpdate: DOMHighResTimeStamp
     private deltaTime: number = 0
 
-    private score: number = 0
+    private needsFullUpload: boolean = true
+    private pendingTileset: { pixels: Uint8Array, width: number, tileSize: number } | null = null
 
     private audioManager: AudioManger = AudioManger.getInstance();
 
+    // Listeners by subscription handle, with the kinds of events they want (empty for all)
+    private listeners: Map<number, { kinds: GameEvent[], listener: EventListener }> = new Map()
+    private nextListenerHandle: number = 1
+
+    // Touches and shoulder buttons turn the snake while the relative key scheme is picked
+    private relativeControls: boolean = false
+    private pressedGamepadButtons: Set<number> = new Set()
+
     constructor(width: number, height: number) {
         this.height = height
         this.width = width
         const canvas = this.initUI()
+        this.canvas = canvas
         this.ctx = canvas.getContext('2d')
         if (!this.ctx) {
             throw new Error('Failed to get 2D context')
@@ -35,12 +54,123 @@ class Game {
 
         this.lastTime = performance.now()
         this.lastFpsUpdate = performance.now()
+        this.subscribe((event: GameEventInfo) => this.audioManager.playAudio(event.kind))
+        this.subscribe(() => this.saveHighScore(), [GameEvent.GameOver])
+        this.subscribe(() => this.saveStats(), [GameEvent.GameOver, GameEvent.LevelLoaded])
+        this.subscribe((event: GameEventInfo) => this.saveAchievement(event), [GameEvent.AchievementUnlocked])
+    }
+
+    private saveAchievement(event: GameEventInfo): void {
+        console.info(`Achievement unlocked: ${event.achievement_name}`)
+        if (this.wasmGame) {
+            localStorage.setItem(ACHIEVEMENTS_KEY, this.wasmGame.get_achievements_text())
+        }
+    }
+
+    private saveStats(): void {
+        if (this.wasmGame) {
+            localStorage.setItem(STATS_KEY, this.wasmGame.get_lifetime_stats_text())
+        }
+    }
+
+    // Keeps good runs with their replay in localStorage
+    private saveHighScore(): void {
+        if (!this.wasmGame?.is_high_score()) {
+            return
+        }
+        const name = window.prompt('New high score! Your name:', 'Player') ?? 'Player'
+        this.wasmGame.submit_high_score(name, Date.now(), true)
+        localStorage.setItem(HIGH_SCORES_KEY, this.wasmGame.get_high_scores_text())
+    }
+
+    private loadHighScores(): void {
+        const text = localStorage.getItem(HIGH_SCORES_KEY)
+        if (!text || !this.wasmGame) {
+            return
+        }
+        try {
+            this.wasmGame.load_high_scores(text)
+        } catch (err: any) {
+            console.error('Error loading high scores:', err)
+        }
+    }
+
+    private loadStats(): void {
+        const text = localStorage.getItem(STATS_KEY)
+        if (!text || !this.wasmGame) {
+            return
+        }
+        try {
+            this.wasmGame.load_lifetime_stats(text)
+        } catch (err: any) {
+            console.error('Error loading stats:', err)
+        }
+    }
+
+    private loadKeyBindings(): void {
+        const text = localStorage.getItem(KEY_BINDINGS_KEY)
+        if (!text || !this.wasmGame) {
+            return
+        }
+        try {
+            this.wasmGame.load_key_bindings(text)
+            this.relativeControls = this.wasmGame.get_keys_for(Action.TurnLeft).length > 0
+        } catch (err: any) {
+            console.error('Error loading key bindings:', err)
+        }
+    }
+
+    private loadAchievements(): void {
+        const text = localStorage.getItem(ACHIEVEMENTS_KEY)
+        if (!text || !this.wasmGame) {
+            return
+        }
+        try {
+            this.wasmGame.load_achievements(text)
+        } catch (err: any) {
+            console.error('Error loading achievements:', err)
+        }
+    }
+
+    // Calls the listener once per frame for every event of the given kinds, or of any kind if
+    // none are given. Returns the handle for unsubscribe.
+    public subscribe(listener: EventListener, kinds: GameEvent[] = []): number {
+        const handle = this.nextListenerHandle++
+        this.listeners.set(handle, { kinds, listener })
+        return handle
+    }
+
+    public unsubscribe(handle: number): boolean {
+        return this.listeners.delete(handle)
+    }
+
+    // Events are polled after the wasm update has returned, so listeners can call into the game
+    private dispatchEvents(): void {
+        if (!this.wasmGame) {
+            return
+        }
+        for (const event of this.wasmGame.poll_events()) {
+            for (const { kinds, listener } of this.listeners.values()) {
+                if (kinds.length === 0 || kinds.includes(event.kind)) {
+                    listener(event)
+                }
+            }
+            event.free()
+        }
     }
 
     private initUI(): HTMLCanvasElement {
         const boardPulldown = document.createElement('select')
         document.body.appendChild(boardPulldown)
 
+        const palettePulldown = document.createElement('select')
+        palettePulldown.title = 'Colour palette'
+        document.body.appendChild(palettePulldown)
+
+        const keysPulldown = document.createElement('select')
+        keysPulldown.title = 'Keys'
+        document.body.appendChild(keysPulldown)
+
         const canvas = document.createElement('canvas')
         canvas.width = this.width
         canvas.height = this.height
@@ -60,6 +190,14 @@ class Game {
                 throw new Error('Failed to initialize WASM game')
             }
             this.imgData = this.setupImageData()
+            this.wasmGame.set_smooth_movement(this.smoothMovement)
+            this.loadHighScores()
+            this.loadStats()
+            this.loadAchievements()
+            this.loadKeyBindings()
+            if (this.pendingTileset) {
+                this.applyTileset(this.pendingTileset)
+            }
 
             document.body.addEventListener('keydown', (e: KeyboardEvent) => {
 
@@ -67,10 +205,6 @@ class Game {
                 this.wasmGame?.key_down(e.code)
             })
 
-            this.wasmGame.add_game_event_listener((event: GameEvent) => {
-                this.audioManager.playAudio(event)
-            })
-
             this.wasmGame.get_level_names().forEach((levelName: string) => {
                 const option = document.createElement('option')
                 option.value = levelName
@@ -78,11 +212,48 @@ class Game {
                 boardPulldown.appendChild(option)
             })
 
+            this.wasmGame.get_palette_names().forEach((paletteName: string) => {
+                const option = document.createElement('option')
+                option.value = paletteName
+                option.text = paletteName
+                palettePulldown.appendChild(option)
+            })
+
+            this.wasmGame.get_key_scheme_names().forEach((schemeName: string) => {
+                const option = document.createElement('option')
+                option.value = schemeName
+                option.text = schemeName
+                keysPulldown.appendChild(option)
+            })
+
+            keysPulldown.addEventListener('change', (e: Event) => {
+                const select = e.target as HTMLSelectElement
+                this.wasmGame?.set_key_scheme(select.value)
+                this.relativeControls = select.value === 'relative'
+                localStorage.setItem(KEY_BINDINGS_KEY, this.wasmGame?.get_key_bindings() ?? '')
+                canvas.focus()
+            })
+
+            canvas.addEventListener('pointerdown', (e: PointerEvent) => {
+                if (this.relativeControls) {
+                    const left = e.offsetX < canvas.clientWidth / 2
+                    this.wasmGame?.press(left ? Action.TurnLeft : Action.TurnRight)
+                }
+            })
+
+            // The menu key pauses the game, the level selection is the closest thing to a menu
+            this.subscribe(() => boardPulldown.focus(), [GameEvent.MenuRequested])
+
+            palettePulldown.addEventListener('change', (e: Event) => {
+                const select = e.target as HTMLSelectElement
+                this.wasmGame?.set_palette(select.value)
+                canvas.focus()
+            })
+
             boardPulldown.addEventListener('change', (e: Event) => {
                 const select = e.target as HTMLSelectElement
                 const levelName = select.value
                 this.wasmGame?.load_level(levelName)
-                this.started = false
                 canvas.focus()
                 this.audioManager?.stopBackgroundMusic()
             })
@@ -104,6 +275,56 @@ class Game {
         this.showPerformanceInfo = show
     }
 
+    // Resizes the canvas, the board is scaled to fit and centred
+    public resize(width: number, height: number): void {
+        this.width = Math.max(1, Math.floor(width))
+        this.height = Math.max(1, Math.floor(height))
+        this.canvas.width = this.width
+        this.canvas.height = this.height
+        if (this.wasmGame) {
+            this.wasmGame.resize(this.width, this.height)
+            this.imgData = this.setupImageData()
+            this.needsFullUpload = true
+        }
+    }
+
+    // Makes the canvas fill the window below the level selection
+    public setFitToWindow(): void {
+        const fit = () => this.resize(window.innerWidth, window.innerHeight - this.canvas.offsetTop)
+        window.addEventListener('resize', fit)
+        fit()
+    }
+
+    public setSmoothMovement(smooth: boolean = true): void {
+        this.smoothMovement = smooth
+        this.wasmGame?.set_smooth_movement(smooth)
+    }
+
+    // Loads a sprite atlas image, see tileset.rs for the order of the tiles
+    public async loadTileset(url: string, tileSize: number): Promise<void> {
+        const response = await fetch(url)
+        const bitmap = await createImageBitmap(await response.blob())
+        const canvas = new OffscreenCanvas(bitmap.width, bitmap.height)
+        const ctx = canvas.getContext('2d')
+        if (!ctx) {
+            throw new Error('Failed to get 2D context for the tileset')
+        }
+        ctx.drawImage(bitmap, 0, 0)
+        const imageData = ctx.getImageData(0, 0, bitmap.width, bitmap.height)
+        this.pendingTileset = {pixels: new Uint8Array(imageData.data.buffer), width: bitmap.width, tileSize}
+        if (this.wasmGame) {
+            this.applyTileset(this.pendingTileset)
+        }
+    }
+
+    private applyTileset(tileset: { pixels: Uint8Array, width: number, tileSize: number }): void {
+        try {
+            this.wasmGame?.load_tileset(tileset.pixels, tileset.width, tileset.tileSize)
+        } catch (err: any) {
+            console.error('Error loading tileset:', err)
+        }
+    }
+
     public async run(): Promise<void> {
         while (true) {
             this.update()
@@ -124,61 +345,74 @@ class Game {
         }
 
         if (this.wasmGame) {
+            this.pollGamepad()
             this.wasmGame.update(this.deltaTime)
-            this.score = this.wasmGame.get_score()
+            this.dispatchEvents()
+        }
+    }
+
+    // Presses turn actions when a shoulder button goes down, holding it doesn't repeat
+    private pollGamepad(): void {
+        const gamepad = navigator.getGamepads().find((pad) => pad !== null)
+        if (!gamepad || !this.relativeControls) {
+            return
+        }
+        for (const [button, action] of GAMEPAD_TURN_BUTTONS) {
+            const pressed = gamepad.buttons[button]?.pressed ?? false
+            if (pressed && !this.pressedGamepadButtons.has(button)) {
+                this.wasmGame?.press(action)
+            }
+            if (pressed) {
+                this.pressedGamepadButtons.add(button)
+            } else {
+                this.pressedGamepadButtons.delete(button)
+            }
         }
     }
 
+    // Score, level and state banners are drawn by wasm, only the performance info is drawn here
     private renderTextLayer(): void {
-        if (!this.ctx || !this.wasmGame) {
+        if (!this.ctx || !this.showPerformanceInfo) {
             return
         }
         const prevAlign = this.ctx.textAlign
         const prevBaseline = this.ctx.textBaseline
 
         const smallFontSize = this.height / 50
-        this.ctx.fillStyle = 'yellow'
+        this.ctx.fillStyle = 'white'
         this.ctx.font = `${smallFontSize}px Arial`
-        this.ctx.textAlign = 'right'
-        this.ctx.textBaseline = 'top'
-        this.ctx.fillText(`Score: ${this.score}`, this.width - 20, 20)
+        this.ctx.textAlign = 'left'
+        this.ctx.textBaseline = 'bottom'
+        this.ctx.fillText(`FPS: ${this.fps}, Delta: ${this.deltaTime}`, 20, this.height - 20)
 
-        if (this.showPerformanceInfo) {
-            this.ctx.fillStyle = 'white'
-            this.ctx.font = `${smallFontSize}px Arial`
-            this.ctx.textAlign = 'left'
-            this.ctx.textBaseline = 'top'
-            this.ctx.fillText(`FPS: ${this.fps}, Delta: ${this.deltaTime}`, 20, 20)
-        }
-
-        const fontSize = this.width / 20
-        const gameState = this.wasmGame.get_game_state()
-
-        this.ctx.textAlign = 'center'
-        this.ctx.textBaseline = 'middle'
-        if (GameState.GameOver === gameState) {
-            this.ctx.fillStyle = 'rgba(0, 0, 0, 0.5)'
-            this.ctx.fillRect(0, 0, this.width, this.height)
-            this.ctx.fillStyle = 'red'
-            this.ctx.font = `${fontSize}px Arial`
-            this.ctx.fillText('Game Over', this.width / 2, this.height / 2)
-        } else if (GameState.Paused === gameState) {
-            this.ctx.fillStyle = 'rgba(0, 0, 0, 0.5)'
-            this.ctx.fillRect(0, 0, this.width, this.height)
-            this.ctx.font = `${fontSize}px Arial`
-            if (!this.started) {
-                this.ctx.fillStyle = 'lightgreen'
-                this.ctx.fillText('Press any space to start', this.width / 2, this.height / 2)
-            } else {
-                this.ctx.fillStyle = 'yellow'
-                this.ctx.fillText('Paused', this.width / 2, this.height / 2)}
-        } else if (GameState.Running === gameState) {
-            this.started = true
-        }
         this.ctx.textAlign = prevAlign
         this.ctx.textBaseline = prevBaseline
     }
 
+    /**
+     * Uploads only the parts of the screen buffer wasm repainted. The performance info is drawn over the board every
+     * frame, so its area is refreshed as well.
+     */
+    private blitScreenBuffer(): void {
+        if (!this.ctx || !this.wasmGame || !this.imgData) {
+            return
+        }
+        if (this.needsFullUpload) {
+            this.ctx.putImageData(this.imgData, 0, 0)
+            this.needsFullUpload = false
+            return
+        }
+
+        const rects = this.wasmGame.get_dirty_rects()
+        for (let i = 0; i < rects.length; i += 4) {
+            this.ctx.putImageData(this.imgData, 0, 0, rects[i], rects[i + 1], rects[i + 2], rects[i + 3])
+        }
+        if (this.showPerformanceInfo) {
+            const textLayerHeight = 40 + this.height / 50
+            this.ctx.putImageData(this.imgData, 0, 0, 0, this.height - textLayerHeight, this.width, textLayerHeight)
+        }
+    }
+
     private async render(): Promise<void> {
         if (!this.ctx || !this.wasmGame || !this.imgData) {
             await new Promise(requestAnimationFrame)
@@ -186,7 +420,13 @@ class Game {
         }
 
         this.wasmGame.render()
-        this.ctx.putImageData(this.imgData, 0, 0)
+        // Growing the wasm memory detaches the buffer the image data looks at
+        if (this.imgData.data.length === 0) {
+            this.imgData = this.setupImageData()
+            this.needsFullUpload = true
+            if (!this.imgData) return
+        }
+        this.blitScreenBuffer()
         this.renderTextLayer();
         await new Promise(requestAnimationFrame)
     }
@@ -4,6 +4,14 @@ import Game from "./Game.ts"
 const game = new Game(1000, 1000)
 // Enable performance info display
 // game.setShowPerformanceInfo()
+// Let the canvas follow the window size
+// game.setFitToWindow()
+// Draw snakes gliding between cells
+// game.setSmoothMovement()
+// Draw the board with sprites instead of flat colours
+// await game.loadTileset('tileset.png', 16)
+// Log why runs end
+// game.subscribe((event) => console.log('game over at', event.x, event.y), [GameEvent.GameOver])
 
 await game.run();
 
@@ -0,0 +1,317 @@
+use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use crate::events::Event;
+use crate::stats::{LifetimeStats, RunStats};
+use crate::GameEvent;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const HEADER: &str = "snake-achievements 1";
+
+/// A number an achievement can ask for.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stat {
+    Score,
+    Length,
+    Speed,
+    FoodEaten,
+    Distance,
+    Turns,
+    LeftTurns,
+    NearMisses,
+    TimeAliveMillis,
+    /// Finished games, from the lifetime stats.
+    GamesPlayed,
+}
+
+/// What has to be true for an achievement to unlock.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    AtLeast(Stat, f64),
+    AtMost(Stat, f64),
+    OnLevel(String),
+    /// `count` events of a kind within `millis` of game time.
+    EventsWithin { kind: GameEvent, count: usize, millis: f64 },
+    All(Vec<Condition>),
+}
+
+/// An achievement described as data, see `built_in` for examples.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Achievement {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub condition: Condition,
+}
+
+impl Achievement {
+    pub fn new(id: &str, name: &str, description: &str, condition: Condition) -> Self {
+        Achievement {
+            id: id.to_string(),
+            name: name.to_string(),
+            description: description.to_string(),
+            condition,
+        }
+    }
+}
+
+/// The achievements that come with the game.
+pub fn built_in() -> Vec<Achievement> {
+    use Condition::*;
+    vec![
+        Achievement::new("first-bite", "First bite", "Eat a piece of food", AtLeast(Stat::FoodEaten, 1.0)),
+        Achievement::new("hungry", "Hungry", "Eat 5 food within 3 seconds", EventsWithin {
+            kind: GameEvent::EatFood,
+            count: 5,
+            millis: 3000.0,
+        }),
+        Achievement::new("long-snake", "Long snake", "Reach length 500", AtLeast(Stat::Length, 500.0)),
+        Achievement::new("speed-demon", "Speed demon", "Move 10 cells per second", AtLeast(Stat::Speed, 10.0)),
+        Achievement::new("close-call", "Close call", "Turn away from 10 obstacles in one game", AtLeast(Stat::NearMisses, 10.0)),
+        Achievement::new("marathon", "Marathon", "Stay alive for 5 minutes", AtLeast(Stat::TimeAliveMillis, 300_000.0)),
+        Achievement::new(
+            "right-minded",
+            "Right-minded",
+            "Score 200 on Board 3 without turning left",
+            All(vec![
+                OnLevel("Board 3".to_string()),
+                AtLeast(Stat::Score, 200.0),
+                AtMost(Stat::LeftTurns, 0.0),
+            ]),
+        ),
+        Achievement::new("veteran", "Veteran", "Play 100 games", AtLeast(Stat::GamesPlayed, 100.0)),
+    ]
+}
+
+/// The state of the game achievements are checked against.
+pub struct Progress<'a> {
+    pub level: &'a str,
+    pub score: u32,
+    pub length: usize,
+    pub run: &'a RunStats,
+    pub lifetime: &'a LifetimeStats,
+}
+
+impl Progress<'_> {
+    fn stat(&self, stat: Stat) -> f64 {
+        match stat {
+            Stat::Score => self.score as f64,
+            Stat::Length => self.length as f64,
+            Stat::Speed => self.run.max_speed as f64,
+            Stat::FoodEaten => self.run.food_eaten as f64,
+            Stat::Distance => self.run.distance as f64,
+            Stat::Turns => self.run.turns as f64,
+            Stat::LeftTurns => self.run.left_turns as f64,
+            Stat::NearMisses => self.run.near_misses as f64,
+            Stat::TimeAliveMillis => self.run.time_alive_millis,
+            Stat::GamesPlayed => self.lifetime.games_played as f64,
+        }
+    }
+}
+
+/// An achievement as JavaScript sees it.
+#[derive(Clone, Debug, PartialEq)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct AchievementInfo {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub unlocked: bool,
+}
+
+/// Checks achievements against the game and remembers which ones are unlocked.
+pub struct Achievements {
+    achievements: Vec<Achievement>,
+    unlocked: Vec<String>,
+    /// Recent events with the game time they happened at, for `Condition::EventsWithin`.
+    recent: VecDeque<(GameEvent, f64)>,
+}
+
+impl Achievements {
+    pub fn new(achievements: Vec<Achievement>) -> Self {
+        Achievements { achievements, unlocked: Vec::new(), recent: VecDeque::new() }
+    }
+
+    pub fn add(&mut self, achievement: Achievement) {
+        self.achievements.push(achievement);
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.iter().any(|unlocked| unlocked == id)
+    }
+
+    pub fn get_infos(&self) -> Vec<AchievementInfo> {
+        self.achievements
+            .iter()
+            .map(|achievement| AchievementInfo {
+                id: achievement.id.clone(),
+                name: achievement.name.clone(),
+                description: achievement.description.clone(),
+                unlocked: self.is_unlocked(&achievement.id),
+            })
+            .collect()
+    }
+
+    /// Forgets the events of the previous game.
+    pub fn start_run(&mut self) {
+        self.recent.clear();
+    }
+
+    /// Remembers an event that happened `time_millis` into the game.
+    pub fn record(&mut self, event: &Event, time_millis: f64) {
+        let longest = self.longest_window();
+        self.recent.push_back((event.kind(), time_millis));
+        while self.recent.front().is_some_and(|&(_, time)| time < time_millis - longest) {
+            self.recent.pop_front();
+        }
+    }
+
+    /// Unlocks every achievement whose condition now holds and returns them.
+    pub fn check(&mut self, progress: &Progress) -> Vec<Achievement> {
+        let newly_unlocked: Vec<Achievement> = self
+            .achievements
+            .iter()
+            .filter(|achievement| !self.is_unlocked(&achievement.id))
+            .filter(|achievement| self.holds(&achievement.condition, progress))
+            .cloned()
+            .collect();
+        self.unlocked.extend(newly_unlocked.iter().map(|achievement| achievement.id.clone()));
+        newly_unlocked
+    }
+
+    fn holds(&self, condition: &Condition, progress: &Progress) -> bool {
+        match condition {
+            Condition::AtLeast(stat, value) => progress.stat(*stat) >= *value,
+            Condition::AtMost(stat, value) => progress.stat(*stat) <= *value,
+            Condition::OnLevel(level) => progress.level == level,
+            Condition::EventsWithin { kind, count, millis } => {
+                let times: Vec<f64> = self.recent.iter().filter(|(k, _)| k == kind).map(|&(_, time)| time).collect();
+                *count > 0 && times.windows(*count).any(|window| window[*count - 1] - window[0] <= *millis)
+            }
+            Condition::All(conditions) => conditions.iter().all(|condition| self.holds(condition, progress)),
+        }
+    }
+
+    fn longest_window(&self) -> f64 {
+        fn window(condition: &Condition) -> f64 {
+            match condition {
+                Condition::EventsWithin { millis, .. } => *millis,
+                Condition::All(conditions) => conditions.iter().map(window).fold(0.0, f64::max),
+                _ => 0.0,
+            }
+        }
+        self.achievements.iter().map(|achievement| window(&achievement.condition)).fold(0.0, f64::max)
+    }
+
+    /// Replaces the unlocked achievements with ones written by `Display`. Unknown ids are kept,
+    /// so unlocks of achievements a later version removes aren't lost.
+    pub fn load(&mut self, text: &str) -> Result<(), String> {
+        self.unlocked = text.parse::<Unlocked>()?.0;
+        Ok(())
+    }
+
+    pub fn unlocked_text(&self) -> String {
+        Unlocked(self.unlocked.clone()).to_string()
+    }
+}
+
+/// The ids of unlocked achievements, one `unlocked <id>` line each.
+struct Unlocked(Vec<String>);
+
+impl Display for Unlocked {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for id in &self.0 {
+            writeln!(f, "unlocked {id}")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Unlocked {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err("Not an achievements file, missing header".to_string());
+        }
+        lines
+            .map(|line| match line.split_once(' ') {
+                Some(("unlocked", id)) => Ok(id.to_string()),
+                _ => Err(format!("Unknown achievements line: {line}")),
+            })
+            .collect::<Result<_, _>>()
+            .map(Unlocked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn progress<'a>(level: &'a str, score: u32, run: &'a RunStats, lifetime: &'a LifetimeStats) -> Progress<'a> {
+        Progress { level, score, length: run.max_length, run, lifetime }
+    }
+
+    #[test]
+    fn achievements_unlock_once_from_stats() {
+        let mut achievements = Achievements::new(built_in());
+        let lifetime = LifetimeStats::new();
+        let mut run = RunStats::new(9, 5.0);
+        assert!(achievements.check(&progress("Board 3", 0, &run, &lifetime)).is_empty());
+
+        run.food_eaten = 1;
+        let unlocked = achievements.check(&progress("Board 3", 10, &run, &lifetime));
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].id, "first-bite");
+        assert!(achievements.check(&progress("Board 3", 10, &run, &lifetime)).is_empty());
+
+        // Scoring 200 without turning left only counts on Board 3
+        assert!(achievements.check(&progress("Board 2", 200, &run, &lifetime)).is_empty());
+        run.left_turns = 1;
+        assert!(achievements.check(&progress("Board 3", 200, &run, &lifetime)).is_empty());
+        run.left_turns = 0;
+        let unlocked = achievements.check(&progress("Board 3", 200, &run, &lifetime));
+        assert_eq!(unlocked[0].id, "right-minded");
+        assert!(achievements.is_unlocked("right-minded"));
+    }
+
+    #[test]
+    fn achievements_count_events_within_a_time() {
+        let mut achievements = Achievements::new(built_in());
+        let (run, lifetime) = (RunStats::new(9, 5.0), LifetimeStats::new());
+        let food = Event::EatFood { x: 0, y: 0, points: 10 };
+        for time in [0.0, 1000.0, 2000.0, 3000.0] {
+            achievements.record(&food, time);
+        }
+        achievements.record(&Event::GamePause, 3100.0);
+        achievements.record(&food, 3500.0);
+        assert!(!achievements.is_unlocked("hungry"));
+        achievements.check(&progress("Board 1", 0, &run, &lifetime));
+        assert!(!achievements.is_unlocked("hungry"));
+
+        achievements.record(&food, 3900.0);
+        achievements.check(&progress("Board 1", 0, &run, &lifetime));
+        assert!(achievements.is_unlocked("hungry"));
+        // The food at 0 ms is older than any window
+        assert_eq!(achievements.recent.len(), 6);
+    }
+
+    #[test]
+    fn achievements_save_unlock_state() {
+        let mut achievements = Achievements::new(built_in());
+        let mut run = RunStats::new(9, 5.0);
+        run.near_misses = 10;
+        achievements.check(&progress("Board 1", 0, &run, &LifetimeStats::new()));
+        let text = achievements.unlocked_text();
+        assert_eq!(text, "snake-achievements 1\nunlocked close-call\n");
+
+        let mut loaded = Achievements::new(built_in());
+        loaded.load(&format!("{text}unlocked removed-one\n")).unwrap();
+        assert!(loaded.is_unlocked("close-call") && loaded.is_unlocked("removed-one"));
+        assert!(loaded.get_infos().iter().any(|info| info.id == "close-call" && info.unlocked));
+        assert!(loaded.load("snake-achievements 1\nlocked close-call").is_err());
+        assert!(loaded.load("unlocked close-call").is_err());
+    }
+}
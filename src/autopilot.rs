@@ -0,0 +1,433 @@
+use std::collections::VecDeque;
+use crate::board::Board;
+use crate::controller::{SnakeController, SurvivalController, World};
+use crate::snake::Snake;
+use crate::Direction;
+
+/// Cells kept free between the head and the tail after a shortcut, so the growth from the food
+/// at its end can't make the snake run into its own tail.
+const SHORTCUT_MARGIN: usize = 4;
+/// Once the snake covers more than this share of the cycle it only follows the cycle.
+const SHORTCUT_MAX_FILL: f32 = 0.5;
+
+type Block = (usize, usize);
+type TreeEdge = (Block, Block, Direction);
+
+/// A closed path through the free cells of a level that visits every cell on it exactly once.
+///
+/// The cycle is built by covering the level with 2x2 blocks, joining the block loops along a
+/// spanning tree and then inserting detours through the cells the blocks left out. Cells that
+/// can't be reached that way (e.g. single cells between walls) are not part of the cycle.
+pub struct HamiltonianCycle {
+    width: usize,
+    cells: Vec<(usize, usize)>,
+    positions: Vec<Option<usize>>,
+}
+
+impl HamiltonianCycle {
+    pub fn new(board: &Board) -> Self {
+        let width = board.get_width();
+        let height = board.get_height();
+        let mut next = vec![None; width * height];
+
+        let best_grid = [(0, 0), (1, 0), (0, 1), (1, 1)]
+            .into_iter()
+            .map(|(offset_x, offset_y)| BlockGrid::new(board, offset_x, offset_y))
+            .map(|grid| {
+                let tree = grid.spanning_tree();
+                (grid, tree)
+            })
+            .max_by_key(|(_, (blocks, _))| blocks.len());
+        if let Some((grid, (blocks, edges))) = best_grid {
+            grid.link_blocks(&blocks, &edges, &mut next);
+        }
+        insert_detours(board, &mut next);
+
+        let mut cells = Vec::new();
+        let mut positions = vec![None; width * height];
+        if let Some(start) = next.iter().position(Option::is_some) {
+            let mut cell = (start % width, start / width);
+            loop {
+                positions[board.xy_to_index(cell.0, cell.1)] = Some(cells.len());
+                cells.push(cell);
+                cell = next[board.xy_to_index(cell.0, cell.1)].unwrap();
+                if board.xy_to_index(cell.0, cell.1) == start {
+                    break;
+                }
+            }
+        }
+
+        HamiltonianCycle {
+            width,
+            cells,
+            positions,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn position(&self, x: usize, y: usize) -> Option<usize> {
+        self.positions[x + y * self.width]
+    }
+
+    /// Returns a grid that is `true` for every cell on the cycle, indexed like the board cells.
+    pub fn covered_cells(&self) -> Vec<bool> {
+        self.positions.iter().map(Option::is_some).collect()
+    }
+
+    /// How many steps along the cycle it takes to get from position `from` to position `to`.
+    fn distance(&self, from: usize, to: usize) -> usize {
+        (to + self.len() - from) % self.len()
+    }
+
+    /// Checks that the whole snake lies on the cycle and its segments appear in cycle order from
+    /// tail to head. As long as that holds the cells ahead of the head up to the tail are free.
+    fn is_ordered(&self, snake: &Snake) -> bool {
+        let (tail_x, tail_y) = snake.get_tail_pos();
+        let Some(tail) = self.position(tail_x, tail_y) else {
+            return false;
+        };
+        let mut previous = None;
+        for &(x, y) in snake.body().rev() {
+            let Some(position) = self.position(x, y) else {
+                return false;
+            };
+            let distance = self.distance(tail, position);
+            if previous.is_some_and(|previous| distance <= previous) {
+                return false;
+            }
+            previous = Some(distance);
+        }
+        true
+    }
+}
+
+/// The level split into 2x2 blocks starting at the given offset. On even sized boards the
+/// blocks wrap around the edges just like the snake does.
+struct BlockGrid<'a> {
+    board: &'a Board,
+    offset_x: usize,
+    offset_y: usize,
+    columns: usize,
+    rows: usize,
+    wrap_x: bool,
+    wrap_y: bool,
+}
+
+impl<'a> BlockGrid<'a> {
+    fn new(board: &'a Board, offset_x: usize, offset_y: usize) -> Self {
+        let width = board.get_width();
+        let height = board.get_height();
+        let wrap_x = width.is_multiple_of(2);
+        let wrap_y = height.is_multiple_of(2);
+        BlockGrid {
+            board,
+            offset_x,
+            offset_y,
+            columns: if wrap_x { width / 2 } else { width.saturating_sub(offset_x) / 2 },
+            rows: if wrap_y { height / 2 } else { height.saturating_sub(offset_y) / 2 },
+            wrap_x,
+            wrap_y,
+        }
+    }
+
+    fn cell(&self, (column, row): Block, dx: usize, dy: usize) -> (usize, usize) {
+        (
+            (self.offset_x + 2 * column + dx) % self.board.get_width(),
+            (self.offset_y + 2 * row + dy) % self.board.get_height(),
+        )
+    }
+
+    fn is_free(&self, block: Block) -> bool {
+        [(0, 0), (1, 0), (0, 1), (1, 1)].into_iter().all(|(dx, dy)| {
+            let (x, y) = self.cell(block, dx, dy);
+            !self.board.is_wall_at(x, y)
+        })
+    }
+
+    fn neighbour(&self, (column, row): Block, direction: Direction) -> Option<Block> {
+        let step = |value: usize, count: usize, wrap: bool, forward: bool| {
+            let next = match (forward, value) {
+                (true, value) if value + 1 < count => Some(value + 1),
+                (true, _) if wrap => Some(0),
+                (false, 0) if wrap => Some(count - 1),
+                (false, value) if value > 0 => Some(value - 1),
+                _ => None,
+            };
+            next.filter(|&next| next != value)
+        };
+        let neighbour = match direction {
+            Direction::Left => step(column, self.columns, self.wrap_x, false).map(|column| (column, row)),
+            Direction::Right => step(column, self.columns, self.wrap_x, true).map(|column| (column, row)),
+            Direction::Up => step(row, self.rows, self.wrap_y, false).map(|row| (column, row)),
+            Direction::Down => step(row, self.rows, self.wrap_y, true).map(|row| (column, row)),
+            Direction::Invalid => None,
+        };
+        neighbour.filter(|&block| self.is_free(block))
+    }
+
+    /// Finds the largest group of connected free blocks and a spanning tree over it. Tree edges
+    /// are stored as `(from, to, direction)`.
+    fn spanning_tree(&self) -> (Vec<Block>, Vec<TreeEdge>) {
+        let mut visited = vec![false; self.columns * self.rows];
+        let mut best = (Vec::new(), Vec::new());
+
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                if visited[column + row * self.columns] || !self.is_free((column, row)) {
+                    continue;
+                }
+                visited[column + row * self.columns] = true;
+                let mut blocks = vec![(column, row)];
+                let mut edges = Vec::new();
+                let mut queue = VecDeque::from([(column, row)]);
+                while let Some(block) = queue.pop_front() {
+                    for direction in Direction::ALL {
+                        if let Some(next) = self.neighbour(block, direction) {
+                            let idx = next.0 + next.1 * self.columns;
+                            if !visited[idx] {
+                                visited[idx] = true;
+                                blocks.push(next);
+                                edges.push((block, next, direction));
+                                queue.push_back(next);
+                            }
+                        }
+                    }
+                }
+                if blocks.len() > best.0.len() {
+                    best = (blocks, edges);
+                }
+            }
+        }
+        best
+    }
+
+    /// Gives every block a clockwise loop and merges neighbouring loops along the tree edges
+    /// into a single cycle.
+    fn link_blocks(&self, blocks: &[Block], edges: &[TreeEdge], next: &mut [Option<(usize, usize)>]) {
+        let index = |(x, y): (usize, usize)| self.board.xy_to_index(x, y);
+        for &block in blocks {
+            let (top_left, top_right) = (self.cell(block, 0, 0), self.cell(block, 1, 0));
+            let (bottom_left, bottom_right) = (self.cell(block, 0, 1), self.cell(block, 1, 1));
+            next[index(top_left)] = Some(top_right);
+            next[index(top_right)] = Some(bottom_right);
+            next[index(bottom_right)] = Some(bottom_left);
+            next[index(bottom_left)] = Some(top_left);
+        }
+
+        for &(from, to, direction) in edges {
+            match direction {
+                Direction::Left | Direction::Right => {
+                    let (left, right) = if direction == Direction::Right { (from, to) } else { (to, from) };
+                    next[index(self.cell(left, 1, 0))] = Some(self.cell(right, 0, 0));
+                    next[index(self.cell(right, 0, 1))] = Some(self.cell(left, 1, 1));
+                }
+                Direction::Up | Direction::Down => {
+                    let (top, bottom) = if direction == Direction::Down { (from, to) } else { (to, from) };
+                    next[index(self.cell(top, 1, 1))] = Some(self.cell(bottom, 1, 0));
+                    next[index(self.cell(bottom, 0, 0))] = Some(self.cell(top, 0, 1));
+                }
+                Direction::Invalid => {}
+            }
+        }
+    }
+}
+
+/// Grows the cycle through pairs of uncovered cells that lie next to one of its edges:
+/// `a -> b` becomes `a -> c -> d -> b`.
+fn insert_detours(board: &Board, next: &mut [Option<(usize, usize)>]) {
+    let is_uncovered = |next: &[Option<(usize, usize)>], (x, y): (usize, usize)| {
+        !board.is_wall_at(x, y) && next[board.xy_to_index(x, y)].is_none()
+    };
+
+    let mut extended = true;
+    while extended {
+        extended = false;
+        for idx in 0..next.len() {
+            let Some(b) = next[idx] else {
+                continue;
+            };
+            let a = (idx % board.get_width(), idx / board.get_width());
+            let Some(direction) = board.direction_between(a, b) else {
+                continue;
+            };
+            let sideways = match direction {
+                Direction::Left | Direction::Right => [Direction::Up, Direction::Down],
+                _ => [Direction::Left, Direction::Right],
+            };
+            for side in sideways {
+                let c = board.neighbour(a.0, a.1, side);
+                let d = board.neighbour(b.0, b.1, side);
+                if c != d
+                    && board.neighbour(c.0, c.1, direction) == d
+                    && is_uncovered(next, c)
+                    && is_uncovered(next, d)
+                {
+                    next[idx] = Some(c);
+                    next[board.xy_to_index(c.0, c.1)] = Some(d);
+                    next[board.xy_to_index(d.0, d.1)] = Some(b);
+                    extended = true;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+/// Steers along a Hamiltonian cycle of the level, which lets the snake fill the whole cycle
+/// without ever trapping itself. While the snake is short it cuts across the cycle towards
+/// food whenever that can't make it overtake its own tail.
+pub struct AutopilotController {
+    cycle: Option<HamiltonianCycle>,
+}
+
+impl AutopilotController {
+    pub fn new() -> Self {
+        AutopilotController { cycle: None }
+    }
+}
+
+impl SnakeController for AutopilotController {
+    fn next_direction(&mut self, world: &World) -> Option<Direction> {
+        let cycle = self.cycle.get_or_insert_with(|| HamiltonianCycle::new(world.board));
+        let snake = world.snake();
+        let (head_x, head_y) = snake.get_head_pos();
+        let blocked = world.blocked_cells();
+        let safe_directions = world.safe_directions(&blocked);
+
+        let Some(head) = cycle.position(head_x, head_y) else {
+            return SurvivalController.next_direction(world);
+        };
+        let successor = cycle.cells[(head + 1) % cycle.len()];
+        let follow = world.board.direction_between((head_x, head_y), successor)
+            .filter(|direction| safe_directions.contains(direction));
+        if follow.is_none() {
+            return SurvivalController.next_direction(world);
+        }
+        if !cycle.is_ordered(snake) {
+            return follow;
+        }
+        shortcut(cycle, world, &safe_directions, head).or(follow)
+    }
+}
+
+/// Picks the move that skips the most of the cycle without passing the nearest food on it or
+/// getting too close to the tail.
+fn shortcut(cycle: &HamiltonianCycle, world: &World, safe_directions: &[Direction], head: usize) -> Option<Direction> {
+    let snake = world.snake();
+    let occupied = snake.len() + snake.get_grow_pending();
+    if occupied as f32 > cycle.len() as f32 * SHORTCUT_MAX_FILL {
+        return None;
+    }
+
+    let (tail_x, tail_y) = snake.get_tail_pos();
+    let free_run = match cycle.distance(head, cycle.position(tail_x, tail_y)?) {
+        0 => cycle.len(),
+        distance => distance,
+    };
+    let food = world
+        .foods
+        .food_positions()
+        .filter_map(|(x, y)| cycle.position(x, y))
+        .map(|food| cycle.distance(head, food))
+        .min()?;
+
+    let (head_x, head_y) = snake.get_head_pos();
+    safe_directions
+        .iter()
+        .filter_map(|&direction| {
+            let (x, y) = world.board.neighbour(head_x, head_y, direction);
+            let skipped = cycle.distance(head, cycle.position(x, y)?);
+            let keeps_distance = skipped + snake.get_grow_pending() + SHORTCUT_MARGIN < free_run;
+            (skipped <= food && keeps_distance).then_some((direction, skipped))
+        })
+        .max_by_key(|&(_, skipped)| skipped)
+        .map(|(direction, _)| direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::food::FoodManager;
+    use crate::snake::MoveOutcome;
+
+    fn walled_board(width: usize, height: usize) -> Board {
+        let mut board = Board::new(width, height, 1, 1);
+        let level_data = (0..width * height)
+            .map(|idx| {
+                let (x, y) = (idx % width, idx / width);
+                if x == 0 || y == 0 || x == width - 1 || y == height - 1 { b'#' } else { b' ' }
+            })
+            .collect();
+        board.set_level_data(&level_data);
+        board
+    }
+
+    fn assert_is_cycle(board: &Board, cycle: &HamiltonianCycle) {
+        for (position, &(x, y)) in cycle.cells.iter().enumerate() {
+            assert!(!board.is_wall_at(x, y));
+            assert_eq!(cycle.position(x, y), Some(position));
+            let next = cycle.cells[(position + 1) % cycle.len()];
+            assert!(board.direction_between((x, y), next).is_some());
+        }
+    }
+
+    #[test]
+    fn cycle_covers_open_board() {
+        let board = Board::new(6, 4, 1, 1);
+        let cycle = HamiltonianCycle::new(&board);
+        assert_eq!(cycle.len(), 24);
+        assert_is_cycle(&board, &cycle);
+    }
+
+    #[test]
+    fn cycle_covers_walled_board() {
+        let board = walled_board(8, 6);
+        let cycle = HamiltonianCycle::new(&board);
+        assert_eq!(cycle.len(), 24);
+        assert_is_cycle(&board, &cycle);
+    }
+
+    #[test]
+    fn cycle_takes_detours_through_cells_left_out_by_blocks() {
+        // The interior is 6x3, so 2x2 blocks can only cover two of its rows
+        let board = walled_board(8, 5);
+        let cycle = HamiltonianCycle::new(&board);
+        assert_eq!(cycle.len(), 18);
+        assert_is_cycle(&board, &cycle);
+    }
+
+    #[test]
+    fn autopilot_fills_the_board() {
+        let board = walled_board(10, 10);
+        let cycle = HamiltonianCycle::new(&board);
+        let mut snakes = [Snake::new(1, 1)];
+        let mut rng = fastrand::Rng::with_seed(1);
+        let mut foods = FoodManager::new();
+        foods.set_spawn_area(Some(cycle.covered_cells()));
+        foods.spawn_food(&board, &snakes, &mut rng);
+
+        // Every food is reached within one lap of the cycle
+        let area = board.get_width() * board.get_height();
+        let mut autopilot = AutopilotController::new();
+        for _ in 0..area * area {
+            let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+            if let Some(direction) = autopilot.next_direction(&world) {
+                snakes[0].set_direction(direction);
+            }
+            assert_eq!(snakes[0].step(&board), MoveOutcome::Moved);
+
+            let (head_x, head_y) = snakes[0].get_head_pos();
+            if foods.is_food_at(head_x, head_y) {
+                foods.take_food(head_x, head_y);
+                snakes[0].grow(2);
+                if !foods.spawn_food(&board, &snakes, &mut rng) {
+                    break;
+                }
+            }
+        }
+        assert_eq!(snakes[0].len(), cycle.len());
+    }
+}
@@ -0,0 +1,283 @@
+//! Runs games without a browser, either driven by a bot or by a recorded replay, and prints how
+//! they ended. Useful for balancing levels and for gameplay regression checks in CI. It can also
+//! save screenshots, level thumbnails and GIF clips of replays.
+
+use std::process::ExitCode;
+use std::path::Path;
+use std::str::FromStr;
+use std::{env, fs};
+use wasm_snake::{ControllerKind, DeathCause, GameWasm, Replay, ScoreClaim};
+
+const USAGE: &str = "Usage: snake_runner [options]
+  --level-file <path>    load a level from a text file ('#' is a wall)
+  --level <name>         level to play (default: the level file or \"Board 1\")
+  --seed <n>             seed of a single run (default: 1)
+  --seeds <from>..<to>   run every seed in the range, end excluded
+  --bot <kind>           greedy, survival or autopilot (default: greedy)
+  --replay <path>        play a recorded game instead of a bot
+  --claim <score>,<len>  check that the replay ends with this score and snake length,
+                         fails if it doesn't
+  --opponents <kinds>    comma separated computer snakes, e.g. greedy,survival
+  --max-ticks <n>        stop a run after this many ticks (default: 1000000)
+  --screenshot <path>    save a PNG of how the last run ended
+  --gif <path>           save the replay as an animated GIF
+  --frame-step <n>       ticks between GIF frames (default: 12)
+  --thumbnails <dir>     save a PNG thumbnail of every level and exit
+  --image-size <w>x<h>   pixel size of screenshots and GIFs, thumbnails use the width
+                         (default: 500x500)";
+
+const DEFAULT_MAX_TICKS: u64 = 1_000_000;
+const DEFAULT_FRAME_STEP: u64 = 12;
+const DEFAULT_IMAGE_SIZE: (usize, usize) = (500, 500);
+
+struct Options {
+    level_file: Option<String>,
+    level: Option<String>,
+    seeds: Vec<u64>,
+    bot: ControllerKind,
+    replay: Option<String>,
+    claim: Option<(u32, usize)>,
+    opponents: Vec<ControllerKind>,
+    max_ticks: u64,
+    screenshot: Option<String>,
+    gif: Option<String>,
+    frame_step: u64,
+    thumbnails: Option<String>,
+    image_size: (usize, usize),
+}
+
+struct RunResult {
+    seed: u64,
+    score: u32,
+    length: usize,
+    ticks: u64,
+    death_cause: Option<DeathCause>,
+}
+
+fn number<T: FromStr>(value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid number: {value}"))
+}
+
+fn parse_options(args: &[String]) -> Result<Options, String> {
+    let mut options = Options {
+        level_file: None,
+        level: None,
+        seeds: vec![1],
+        bot: ControllerKind::Greedy,
+        replay: None,
+        claim: None,
+        opponents: Vec::new(),
+        max_ticks: DEFAULT_MAX_TICKS,
+        screenshot: None,
+        gif: None,
+        frame_step: DEFAULT_FRAME_STEP,
+        thumbnails: None,
+        image_size: DEFAULT_IMAGE_SIZE,
+    };
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--level-file" => options.level_file = Some(value()?),
+            "--level" => options.level = Some(value()?),
+            "--seed" => options.seeds = vec![number(&value()?)?],
+            "--seeds" => {
+                let range = value()?;
+                let (from, to) = range
+                    .split_once("..")
+                    .ok_or_else(|| format!("Invalid seed range: {range}"))?;
+                options.seeds = (number(from)?..number(to)?).collect();
+                if options.seeds.is_empty() {
+                    return Err(format!("Empty seed range: {range}"));
+                }
+            }
+            "--bot" => options.bot = value()?.parse()?,
+            "--replay" => options.replay = Some(value()?),
+            "--claim" => {
+                let claim = value()?;
+                let (score, length) = claim
+                    .split_once(',')
+                    .ok_or_else(|| format!("Invalid claim: {claim}"))?;
+                options.claim = Some((number(score)?, number(length)?));
+            }
+            "--opponents" => {
+                options.opponents = value()?
+                    .split(',')
+                    .filter(|kind| !kind.is_empty())
+                    .map(str::parse)
+                    .collect::<Result<_, _>>()?;
+            }
+            "--max-ticks" => options.max_ticks = number(&value()?)?,
+            "--screenshot" => options.screenshot = Some(value()?),
+            "--gif" => options.gif = Some(value()?),
+            "--frame-step" => options.frame_step = number(&value()?)?,
+            "--thumbnails" => options.thumbnails = Some(value()?),
+            "--image-size" => {
+                let size = value()?;
+                let (width, height) = size
+                    .split_once('x')
+                    .ok_or_else(|| format!("Invalid image size: {size}"))?;
+                options.image_size = (number(width)?, number(height)?);
+            }
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown option: {arg}\n{USAGE}")),
+        }
+    }
+    if options.gif.is_some() && options.replay.is_none() {
+        return Err("--gif needs a --replay to record".to_string());
+    }
+    if options.claim.is_some() && options.replay.is_none() {
+        return Err("--claim needs the --replay of the game".to_string());
+    }
+    Ok(options)
+}
+
+fn write_file(path: &str, bytes: &[u8]) -> Result<(), String> {
+    fs::write(path, bytes).map_err(|err| format!("Can't write {path}: {err}"))
+}
+
+/// Saves a thumbnail of every level as `<dir>/<level name>.png`.
+fn write_thumbnails(game: &GameWasm, dir: &str, size: usize) -> Result<(), String> {
+    fs::create_dir_all(dir).map_err(|err| format!("Can't create {dir}: {err}"))?;
+    for level in game.get_level_names() {
+        let png = game.level_thumbnail_png(&level, size).map_err(|err| err.to_string())?;
+        let path = Path::new(dir).join(format!("{level}.png"));
+        write_file(&path.to_string_lossy(), &png)?;
+    }
+    Ok(())
+}
+
+fn result_of(game: &GameWasm, seed: u64) -> RunResult {
+    RunResult {
+        seed,
+        score: game.get_score(),
+        length: game.get_snake_length(),
+        ticks: game.get_ticks(),
+        death_cause: game.get_death_cause(),
+    }
+}
+
+fn run(options: &Options) -> Result<Vec<RunResult>, String> {
+    let mut game = GameWasm::new(100, 100);
+    let replay = match &options.replay {
+        Some(path) => {
+            let text = fs::read_to_string(path).map_err(|err| format!("Can't read {path}: {err}"))?;
+            Some(text.parse::<Replay>()?)
+        }
+        None => None,
+    };
+
+    let mut level = options.level.clone().unwrap_or_else(|| "Board 1".to_string());
+    if let Some(path) = &options.level_file {
+        let level_data = fs::read(path).map_err(|err| format!("Can't read {path}: {err}"))?;
+        // A level file replaces the level the replay was recorded on
+        let name = match (&replay, &options.level) {
+            (Some(replay), _) => replay.level.clone(),
+            (None, Some(name)) => name.clone(),
+            (None, None) => Path::new(path).file_stem().unwrap_or_default().to_string_lossy().to_string(),
+        };
+        game.add_level(&name, &level_data).map_err(|err| format!("Invalid level {path}: {err}"))?;
+        level = name;
+    }
+
+    if let Some(dir) = &options.thumbnails {
+        write_thumbnails(&game, dir, options.image_size.0)?;
+        return Ok(Vec::new());
+    }
+
+    let (image_width, image_height) = options.image_size;
+    if let Some(replay) = &replay {
+        if let Some((score, length)) = options.claim {
+            let claim = ScoreClaim { replay: replay.clone(), mode: replay.mode(), score, length };
+            game.verify_claim(&claim).map_err(|rejection| format!("claim rejected: {rejection}"))?;
+            println!("claim verified");
+            return Ok(vec![result_of(&game, replay.seed)]);
+        }
+        match &options.gif {
+            Some(path) => {
+                let gif = game
+                    .replay_gif(replay, image_width, image_height, options.frame_step)
+                    .map_err(|err| err.to_string())?;
+                write_file(path, &gif)?;
+            }
+            None => game.play_replay(replay).map_err(|err| err.to_string())?,
+        }
+        save_screenshot(&mut game, options)?;
+        return Ok(vec![result_of(&game, replay.seed)]);
+    }
+
+    if !game.get_level_names().contains(&level) {
+        return Err(format!("Unknown level: {level}"));
+    }
+    game.load_level(&level);
+    game.set_player_controller(options.bot);
+    for &kind in &options.opponents {
+        game.add_computer_snake(kind);
+    }
+
+    let results = options
+        .seeds
+        .iter()
+        .map(|&seed| {
+            game.reset_with_seed(seed);
+            game.run_until_game_over(options.max_ticks);
+            result_of(&game, seed)
+        })
+        .collect();
+    save_screenshot(&mut game, options)?;
+    Ok(results)
+}
+
+fn save_screenshot(game: &mut GameWasm, options: &Options) -> Result<(), String> {
+    if let Some(path) = &options.screenshot {
+        let (width, height) = options.image_size;
+        game.resize(width, height);
+        game.render();
+        let png = game.screenshot_png().map_err(|err| err.to_string())?;
+        write_file(path, &png)?;
+    }
+    Ok(())
+}
+
+fn print_summary<T: Copy + Into<f64>>(name: &str, values: impl Iterator<Item = T>) {
+    let mut values: Vec<f64> = values.map(Into::into).collect();
+    values.sort_by(f64::total_cmp);
+    let mean = values.iter().sum::<f64>() / values.len() as f64;
+    let median = values[values.len() / 2];
+    println!(
+        "{name:>6}: min {:.0}, mean {mean:.1}, median {median:.0}, max {:.0}",
+        values[0],
+        values[values.len() - 1]
+    );
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let results = match parse_options(&args).and_then(|options| run(&options)) {
+        Ok(results) => results,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    for result in &results {
+        let state = match result.death_cause {
+            Some(cause) => format!("game over: {}", cause.to_string().to_lowercase()),
+            None => "still alive".to_string(),
+        };
+        println!(
+            "seed {}: score {}, length {}, ticks {}, {state}",
+            result.seed, result.score, result.length, result.ticks
+        );
+    }
+
+    if results.len() > 1 {
+        println!("{} runs", results.len());
+        print_summary("score", results.iter().map(|result| result.score));
+        print_summary("length", results.iter().map(|result| result.length as u32));
+        print_summary("ticks", results.iter().map(|result| result.ticks as f64));
+    }
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,172 @@
+//! Plays the game in a terminal. Handy for trying levels over SSH without building the frontend.
+
+use std::io::{self, Write};
+use std::process::ExitCode;
+use std::time::{Duration, Instant};
+use std::{env, fs};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::{cursor, execute, queue, terminal};
+use wasm_snake::{ControllerKind, GameState, GameWasm};
+
+const USAGE: &str = "Usage: snake_tui [options]
+  --level-file <path>    play a level from a text file ('#' is a wall), sized by its lines
+                         if they all have the same length, 100x100 otherwise
+  --opponents <kinds>    comma separated computer snakes, e.g. greedy,survival
+  --palette <name|path>  colour palette, a built-in one or a palette file
+  --keys <scheme>        arrows, wasd, hjkl or relative, which only turns left and right
+                         (default: arrows)
+
+Keys: arrows steer, space starts and pauses, r restarts, Esc pauses like the menu key of the
+browser game, 1-9 pick a level, p switches palettes, q quits.";
+
+const FRAME_TIME: Duration = Duration::from_millis(33);
+
+/// Puts the terminal into raw mode on an alternate screen and restores it when dropped, even
+/// if the game panics.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(TerminalGuard)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// The `KeyboardEvent.code` a browser would report for a key.
+fn key_name(code: KeyCode) -> Option<String> {
+    let name = match code {
+        KeyCode::Up => "ArrowUp",
+        KeyCode::Down => "ArrowDown",
+        KeyCode::Left => "ArrowLeft",
+        KeyCode::Right => "ArrowRight",
+        KeyCode::Enter => "Enter",
+        KeyCode::Esc => "Escape",
+        KeyCode::Char(' ') => "Space",
+        KeyCode::Char(letter) if letter.is_ascii_alphabetic() => {
+            return Some(format!("Key{}", letter.to_ascii_uppercase()));
+        }
+        _ => return None,
+    };
+    Some(name.to_string())
+}
+
+fn setup_game(args: &[String]) -> Result<GameWasm, String> {
+    let mut game = GameWasm::new(100, 100);
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().cloned().ok_or_else(|| format!("Missing value for {arg}"));
+        match arg.as_str() {
+            "--level-file" => {
+                let path = value()?;
+                let level_data = fs::read(&path).map_err(|err| format!("Can't read {path}: {err}"))?;
+                game.add_level(&path, &level_data).map_err(|err| format!("Invalid level {path}: {err}"))?;
+                game.load_level(&path);
+            }
+            "--opponents" => {
+                for kind in value()?.split(',').filter(|kind| !kind.is_empty()) {
+                    game.add_computer_snake(kind.parse::<ControllerKind>()?);
+                }
+            }
+            "--palette" => {
+                let palette = value()?;
+                if game.set_palette(&palette).is_err() {
+                    let text = fs::read_to_string(&palette).map_err(|err| format!("Can't read {palette}: {err}"))?;
+                    game.load_palette(&text).map_err(|err| format!("Invalid palette {palette}: {err}"))?;
+                }
+            }
+            "--keys" => game.set_key_scheme(&value()?)?,
+            "--help" | "-h" => return Err(USAGE.to_string()),
+            _ => return Err(format!("Unknown option: {arg}\n{USAGE}")),
+        }
+    }
+    Ok(game)
+}
+
+fn status_line(game: &GameWasm) -> String {
+    let state = match game.get_game_state() {
+        GameState::Running => "",
+        GameState::Paused => " - PAUSED, press space",
+        GameState::GameOver => " - GAME OVER, press space",
+    };
+    format!(
+        "{} | Score: {} | Length: {}{state}\x1b[K\r\n",
+        game.get_level_name(),
+        game.get_score(),
+        game.get_snake_length()
+    )
+}
+
+fn play(mut game: GameWasm) -> io::Result<()> {
+    let _guard = TerminalGuard::new()?;
+    let level_names = game.get_level_names();
+    let palette_names = game.get_palette_names();
+    let mut palette = 0;
+    let mut stdout = io::stdout();
+    let mut last_frame = Instant::now();
+
+    loop {
+        while event::poll(Duration::ZERO)? {
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind == KeyEventKind::Release {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char('q') => return Ok(()),
+                KeyCode::Char('p') => {
+                    palette = (palette + 1) % palette_names.len();
+                    let _ = game.set_palette(&palette_names[palette]);
+                }
+                KeyCode::Char(digit @ '1'..='9') => {
+                    let index = digit as usize - '1' as usize;
+                    if let Some(name) = level_names.get(index) {
+                        game.load_level(name);
+                    }
+                }
+                code => {
+                    if let Some(name) = key_name(code) {
+                        game.key_down(&name);
+                    }
+                }
+            }
+        }
+
+        let now = Instant::now();
+        game.update(now.duration_since(last_frame).as_secs_f32() * 1000.0);
+        last_frame = now;
+
+        queue!(stdout, cursor::MoveTo(0, 0))?;
+        stdout.write_all(game.render_ansi().as_bytes())?;
+        stdout.write_all(status_line(&game).as_bytes())?;
+        stdout.flush()?;
+
+        std::thread::sleep(FRAME_TIME.saturating_sub(now.elapsed()));
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let game = match setup_game(&args) {
+        Ok(game) => game,
+        Err(message) => {
+            eprintln!("{message}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match play(game) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Terminal error: {err}");
+            ExitCode::FAILURE
+        }
+    }
+}
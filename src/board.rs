@@ -1,14 +1,47 @@
-use crate::{Color, GameObject};
+use crate::{Color, Direction, GameObject, SnakePart};
+use crate::canvas::Rect;
 use crate::level::Level;
+use crate::palette::Palette;
+use crate::tileset::Tileset;
+use crate::viewport::Viewport;
+
+const MIN_EYE_CELL_SIZE: usize = 4;
+const LETTERBOX: Color = Color { r: 0, g: 0, b: 0, a: 255 };
+
+/// Part of a cell painted on top of the grid, e.g. a snake head halfway into its next cell.
+#[derive(PartialEq)]
+struct Motion {
+    x: usize,
+    y: usize,
+    edge: Direction,
+    fraction: f32,
+    object: GameObject,
+}
 
 #[derive(PartialEq)]
 pub struct Board {
     width: usize,
     height: usize,
-    cell_width: usize,
-    cell_height: usize,
+    screen_width: usize,
+    screen_height: usize,
+    scale_x: f64,
+    scale_y: f64,
+    offset_x: usize,
+    offset_y: usize,
+    clear_screen: bool,
+    min_cell_size: usize,
+    viewport: Viewport,
     cells: Vec<GameObject>,
     level_data: Vec<u8>,
+    level_cells: Vec<GameObject>,
+    /// What was painted at each cell of the viewport, row by row.
+    painted_cells: Vec<Option<GameObject>>,
+    dirty_rects: Vec<u32>,
+    tileset: Option<Tileset>,
+    palette: Palette,
+    smooth_movement: bool,
+    motions: Vec<Motion>,
+    overlays: Vec<Rect>,
 }
 
 impl Board {
@@ -19,18 +52,87 @@ impl Board {
         Board {
             width,
             height,
-            cell_width,
-            cell_height,
+            screen_width: width * cell_width,
+            screen_height: height * cell_height,
+            scale_x: cell_width as f64,
+            scale_y: cell_height as f64,
+            offset_x: 0,
+            offset_y: 0,
+            clear_screen: false,
+            min_cell_size: 0,
+            viewport: Viewport::new(width, height, width, height),
             cells,
             level_data: vec![b' '; size],
+            level_cells: vec![GameObject::Empty; size],
+            painted_cells: vec![None; size],
+            dirty_rects: Vec::new(),
+            tileset: None,
+            palette: Palette::default(),
+            smooth_movement: false,
+            motions: Vec::new(),
+            overlays: Vec::new(),
         }
     }
 
     pub fn set_level_data(&mut self, level: &Level) {
-            self.level_data = level.to_owned();
+        self.level_data = level.to_owned();
+        self.level_cells = self
+            .level_data
+            .iter()
+            .map(|&char_byte| if char_byte == b'#' { GameObject::Wall } else { GameObject::Empty })
+            .collect();
+        // Wall sprites depend on the neighbouring walls, so none of the painted walls can be kept
+        self.invalidate();
     }
 
-    fn xy_to_index(&self, x: usize, y: usize) -> usize {
+    /// Changes the size of the board, which empties it. The screen layout adapts to the new size.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let size = width * height;
+        self.width = width;
+        self.height = height;
+        self.cells = vec![GameObject::Empty; size];
+        self.level_data = vec![b' '; size];
+        self.level_cells = vec![GameObject::Empty; size];
+        self.motions.clear();
+        self.set_screen_size(self.screen_width, self.screen_height);
+    }
+
+    pub fn get_palette(&self) -> &Palette {
+        &self.palette
+    }
+
+    /// Draws cells without a tileset in the colours of `palette`.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.palette = palette;
+        self.invalidate();
+    }
+
+    pub fn get_smooth_movement(&self) -> bool {
+        self.smooth_movement
+    }
+
+    /// Turns on drawing snakes partway between cells, see `add_motion`.
+    pub fn set_smooth_movement(&mut self, smooth_movement: bool) {
+        self.smooth_movement = smooth_movement;
+        self.invalidate();
+    }
+
+    /// Paints `fraction` of the cell at `(x, y)`, starting at its `edge` side, with `object` on
+    /// top of the grid in the next `render_to_buffer`. Only used with smooth movement and flat
+    /// colours, tiles are always drawn whole.
+    pub fn add_motion(&mut self, x: usize, y: usize, edge: Direction, fraction: f32, object: GameObject) {
+        if self.smooth_movement && self.tileset.is_none() && x < self.width && y < self.height {
+            self.motions.push(Motion { x, y, edge, fraction: fraction.clamp(0.0, 1.0), object });
+        }
+    }
+
+    /// Draws cells with sprites from `tileset`, or with flat colours when it is `None`.
+    pub fn set_tileset(&mut self, tileset: Option<Tileset>) {
+        self.tileset = tileset;
+        self.invalidate();
+    }
+
+    pub fn xy_to_index(&self, x: usize, y: usize) -> usize {
         x + y * self.width
     }
 
@@ -41,7 +143,6 @@ impl Board {
         }
     }
 
-    #[allow(dead_code)]
     pub fn get_cell(&self, x: usize, y: usize) -> Option<GameObject> {
         if x < self.width && y < self.height {
             let idx = self.xy_to_index(x, y);
@@ -58,18 +159,28 @@ impl Board {
         self.height
     }
 
+    /// Returns the cell next to `(x, y)` in the given direction. Leaving the board on one side
+    /// enters it again on the opposite side.
+    pub fn neighbour(&self, x: usize, y: usize, direction: Direction) -> (usize, usize) {
+        match direction {
+            Direction::Up => (x, if y == 0 { self.height - 1 } else { y - 1 }),
+            Direction::Down => (x, if y == self.height - 1 { 0 } else { y + 1 }),
+            Direction::Left => (if x == 0 { self.width - 1 } else { x - 1 }, y),
+            Direction::Right => (if x == self.width - 1 { 0 } else { x + 1 }, y),
+            Direction::Invalid => (x, y),
+        }
+    }
+
+    /// Returns the direction that leads from one cell to a neighbouring one, if they are neighbours.
+    pub fn direction_between(&self, from: (usize, usize), to: (usize, usize)) -> Option<Direction> {
+        Direction::ALL
+            .into_iter()
+            .find(|&direction| self.neighbour(from.0, from.1, direction) == to)
+    }
+
     pub fn draw_level(&mut self) {
-        self.level_data
-            .iter()
-            .enumerate()
-            .for_each(|(idx, char_byte)| {
-                let game_object = if *char_byte == b'#' {
-                    GameObject::Wall
-                } else {
-                    GameObject::Empty
-                };
-                self.cells[idx] = game_object;
-            });
+        self.cells.copy_from_slice(&self.level_cells);
+        self.motions.clear();
     }
 
     pub fn is_wall_at(&self, x: usize, y: usize) -> bool {
@@ -77,28 +188,247 @@ impl Board {
         self.level_data[idx] == b'#'
     }
 
+    pub fn get_screen_width(&self) -> usize {
+        self.screen_width
+    }
+    pub fn get_screen_height(&self) -> usize {
+        self.screen_height
+    }
+
+    /// Fits the board into a pixel buffer of any size. Cells stay square and may be a fraction
+    /// of a pixel wide, so neighbouring cells can differ by a pixel. The board is centred and the
+    /// rest of the buffer is filled with black bars.
+    ///
+    /// If the cells would get smaller than the minimum cell size, they keep that size instead and
+    /// only the part of the board in the viewport is shown.
+    pub fn set_screen_size(&mut self, screen_width: usize, screen_height: usize) {
+        let fit_scale = (screen_width as f64 / self.width as f64).min(screen_height as f64 / self.height as f64);
+        let scale = fit_scale.max(self.min_cell_size as f64);
+        let columns = (screen_width as f64 / scale) as usize;
+        let rows = (screen_height as f64 / scale) as usize;
+        let (x, y) = self.viewport.get_position();
+        self.viewport = Viewport::new(self.width, self.height, columns, rows);
+        self.viewport.scroll_to(x, y);
+
+        self.screen_width = screen_width;
+        self.screen_height = screen_height;
+        self.scale_x = scale;
+        self.scale_y = scale;
+        let (columns, rows) = (self.viewport.get_columns(), self.viewport.get_rows());
+        self.offset_x = screen_width.saturating_sub((columns as f64 * scale).round() as usize) / 2;
+        self.offset_y = screen_height.saturating_sub((rows as f64 * scale).round() as usize) / 2;
+        self.painted_cells = vec![None; columns * rows];
+        self.invalidate();
+    }
+
+    /// The smallest width of a cell in pixels, 0 always fits the whole board onto the screen.
+    pub fn set_min_cell_size(&mut self, min_cell_size: usize) {
+        self.min_cell_size = min_cell_size;
+        self.set_screen_size(self.screen_width, self.screen_height);
+    }
+
+    pub fn get_viewport(&self) -> &Viewport {
+        &self.viewport
+    }
+
+    /// Scrolls the viewport to keep the cell `(x, y)` in view, see `Viewport::follow`.
+    pub fn follow(&mut self, x: usize, y: usize) {
+        if self.viewport.follow(x, y) {
+            self.painted_cells.fill(None);
+        }
+    }
+
+    /// The pixel rectangle `(left, top, width, height)` a cell is drawn into, if it is in the
+    /// viewport.
+    pub fn cell_rect(&self, x: usize, y: usize) -> Option<Rect> {
+        let (column, row) = self.viewport.screen_cell(x, y)?;
+        Some(self.screen_rect(column, row))
+    }
+
+    /// The size of a cell in pixels. Cells can be a pixel smaller than this.
+    pub fn cell_size(&self) -> (usize, usize) {
+        let (_, _, width, height) = self.screen_rect(0, 0);
+        (width, height)
+    }
+
+    /// The pixel rectangle of a column and row of the viewport.
+    fn screen_rect(&self, column: usize, row: usize) -> Rect {
+        let (left, right) = span(self.offset_x, self.scale_x, column);
+        let (top, bottom) = span(self.offset_y, self.scale_y, row);
+        (left, top, right - left, bottom - top)
+    }
+
+    /// Forgets what is in the pixel buffer, so the next render repaints every cell, and the
+    /// letterbox bars if there are any.
+    pub fn invalidate(&mut self) {
+        self.painted_cells.fill(None);
+        let (columns, rows) = (self.viewport.get_columns(), self.viewport.get_rows());
+        let (left, top, width, height) = self.screen_rect(columns - 1, rows - 1);
+        self.clear_screen = self.offset_x > 0
+            || self.offset_y > 0
+            || left + width != self.screen_width
+            || top + height != self.screen_height;
+    }
+
+    /// Pixel rectangles repainted by the last `render_to_buffer`, as flat `x, y, width, height`
+    /// quadruples. Dirty cells next to each other in a row share one rectangle.
+    pub fn get_dirty_rects(&self) -> &[u32] {
+        &self.dirty_rects
+    }
+
+    /// Paints the cells that changed since the last call into the RGBA buffer and leaves all
+    /// other pixels untouched.
     pub fn render_to_buffer(&mut self, buffer: &mut [u8]) {
-        for y in 0..self.height {
-            for x in 0..self.width {
-                let idx = self.xy_to_index(x, y);
-                let color = Color::from(self.cells[idx]);
-
-                for cy in 0..self.cell_height {
-                    for cx in 0..self.cell_width {
-                        let buffer_x = x * self.cell_width + cx;
-                        let buffer_y = y * self.cell_height + cy;
-                        let index = (buffer_y * self.width * self.cell_width + buffer_x) * 4;
-                        buffer[index] = color.r;
-                        buffer[index + 1] = color.g;
-                        buffer[index + 2] = color.b;
-                        buffer[index + 3] = color.a;
-                    }
+        self.dirty_rects.clear();
+        if self.clear_screen {
+            self.fill_rect(buffer, 0, 0, self.screen_width, self.screen_height, LETTERBOX);
+            self.dirty_rects.extend([0, 0, self.screen_width as u32, self.screen_height as u32]);
+            self.clear_screen = false;
+        }
+        for overlay in std::mem::take(&mut self.overlays) {
+            self.clear_overlay(buffer, overlay);
+        }
+        let (columns, rows) = (self.viewport.get_columns(), self.viewport.get_rows());
+        for row in 0..rows {
+            let mut run_start = None;
+            for column in 0..=columns {
+                let painted = row * columns + column;
+                let (x, y) = self.viewport.board_cell(column, row);
+                let object = self.cells[self.xy_to_index(x, y)];
+                if column < columns && self.painted_cells[painted] != Some(object) {
+                    self.paint_cell(buffer, x, y, self.screen_rect(column, row));
+                    self.painted_cells[painted] = Some(object);
+                    run_start.get_or_insert(column);
+                } else if let Some(start) = run_start.take() {
+                    let (left, top, _, height) = self.screen_rect(start, row);
+                    let (last_left, _, last_width, _) = self.screen_rect(column - 1, row);
+                    self.dirty_rects.extend([left, top, last_left + last_width - left, height].map(|value| value as u32));
+                }
+            }
+        }
+        self.paint_motions(buffer);
+    }
+
+    fn paint_motions(&mut self, buffer: &mut [u8]) {
+        for motion in std::mem::take(&mut self.motions) {
+            let Some((left, top, cell_width, cell_height)) = self.cell_rect(motion.x, motion.y) else {
+                continue;
+            };
+            let covered = |size: usize| (motion.fraction * size as f32).round() as usize;
+            let (x, y, width, height) = match motion.edge {
+                Direction::Up => (0, 0, cell_width, covered(cell_height)),
+                Direction::Down => {
+                    let height = covered(cell_height);
+                    (0, cell_height - height, cell_width, height)
                 }
+                Direction::Left => (0, 0, covered(cell_width), cell_height),
+                Direction::Right => {
+                    let width = covered(cell_width);
+                    (cell_width - width, 0, width, cell_height)
+                }
+                Direction::Invalid => continue,
+            };
+            if width == 0 || height == 0 {
+                continue;
+            }
+
+            let color = self.palette.color(motion.object);
+            self.fill_rect(buffer, left + x, top + y, width, height, color);
+            self.add_overlay((left + x, top + y, width, height));
+        }
+    }
+
+    /// Tells the board something was drawn over `rect` of the pixel buffer after it was
+    /// rendered. The rectangle is reported as dirty and restored by the next render.
+    pub fn add_overlay(&mut self, rect: Rect) {
+        let (left, top, width, height) = rect;
+        self.dirty_rects.extend([left, top, width, height].map(|value| value as u32));
+        self.overlays.push(rect);
+    }
+
+    /// Blanks an overlay and makes the cells below it repaint.
+    fn clear_overlay(&mut self, buffer: &mut [u8], (left, top, width, height): Rect) {
+        self.fill_rect(buffer, left, top, width, height, LETTERBOX);
+        self.dirty_rects.extend([left, top, width, height].map(|value| value as u32));
+        let overlaps = |offset: usize, scale: f64, index: usize, start: usize, size: usize| {
+            let (cell_start, cell_end) = span(offset, scale, index);
+            cell_start < start + size && cell_end > start
+        };
+        let columns = self.viewport.get_columns();
+        for row in (0..self.viewport.get_rows()).filter(|&row| overlaps(self.offset_y, self.scale_y, row, top, height)) {
+            for column in (0..columns).filter(|&column| overlaps(self.offset_x, self.scale_x, column, left, width)) {
+                self.painted_cells[row * columns + column] = None;
+            }
+        }
+    }
+
+    fn paint_cell(&self, buffer: &mut [u8], x: usize, y: usize, rect: Rect) {
+        let object = self.cells[self.xy_to_index(x, y)];
+        let (left, top, width, height) = rect;
+        if let Some(tileset) = &self.tileset {
+            let wall_neighbours = [Direction::Up, Direction::Right, Direction::Down, Direction::Left]
+                .into_iter()
+                .enumerate()
+                .filter(|&(_, direction)| {
+                    let (next_x, next_y) = self.neighbour(x, y, direction);
+                    self.is_wall_at(next_x, next_y)
+                })
+                .fold(0, |mask, (bit, _)| mask | 1 << bit);
+            tileset.paint(buffer, self.screen_width, self.screen_height, left, top, width, height, object, wall_neighbours);
+            return;
+        }
+
+        let color = self.palette.color(object);
+        self.fill_rect(buffer, left, top, width, height, color);
+        if let GameObject::Snake(SnakePart::Head(direction)) | GameObject::Opponent(SnakePart::Head(direction)) = object {
+            self.paint_eyes(buffer, rect, direction);
+        }
+    }
+
+    /// Puts two eyes near the front edge of a head cell, so it's clear which way the snake is
+    /// going. Cells smaller than `MIN_EYE_CELL_SIZE` pixels have no room for them.
+    fn paint_eyes(&self, buffer: &mut [u8], (cell_left, cell_top, width, height): Rect, direction: Direction) {
+        if width.min(height) < MIN_EYE_CELL_SIZE {
+            return;
+        }
+        let size = (width.min(height) / 5).max(1);
+        let across = |length: usize| [length / 4, length * 3 / 4].map(|middle| middle - size / 2);
+        let eyes = match direction {
+            Direction::Up => across(width).map(|eye_x| (eye_x, height / 5)),
+            Direction::Down => across(width).map(|eye_x| (eye_x, height - height / 5 - size)),
+            Direction::Left => across(height).map(|eye_y| (width / 5, eye_y)),
+            Direction::Right => across(height).map(|eye_y| (width - width / 5 - size, eye_y)),
+            Direction::Invalid => return,
+        };
+        for (eye_x, eye_y) in eyes {
+            self.fill_rect(buffer, cell_left + eye_x, cell_top + eye_y, size, size, self.palette.eye_color());
+        }
+    }
+
+    /// Fills a rectangle of the buffer, leaving out the parts that are off the screen. A cell can
+    /// be bigger than a tiny screen.
+    fn fill_rect(&self, buffer: &mut [u8], left: usize, top: usize, width: usize, height: usize, color: Color) {
+        let right = (left + width).min(self.screen_width);
+        let bottom = (top + height).min(self.screen_height);
+        for buffer_y in top..bottom {
+            for buffer_x in left..right {
+                let index = (buffer_y * self.screen_width + buffer_x) * 4;
+                buffer[index] = color.r;
+                buffer[index + 1] = color.g;
+                buffer[index + 2] = color.b;
+                buffer[index + 3] = color.a;
             }
         }
     }
 }
 
+/// The pixels `start..end` the cell at `index` covers along one axis.
+fn span(offset: usize, scale: f64, index: usize) -> (usize, usize) {
+    let start = offset + (index as f64 * scale).round() as usize;
+    let end = offset + ((index + 1) as f64 * scale).round() as usize;
+    (start, end)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -139,6 +469,24 @@ mod tests {
         assert!(board.is_wall_at(2, 2));
     }
 
+    #[test]
+    fn board_neighbour_wraps_around_edges() {
+        let board = Board::new(5, 5, 1, 1);
+        assert_eq!(board.neighbour(2, 2, Direction::Up), (2, 1));
+        assert_eq!(board.neighbour(0, 0, Direction::Up), (0, 4));
+        assert_eq!(board.neighbour(0, 0, Direction::Left), (4, 0));
+        assert_eq!(board.neighbour(4, 4, Direction::Right), (0, 4));
+        assert_eq!(board.neighbour(4, 4, Direction::Down), (4, 0));
+    }
+
+    #[test]
+    fn board_finds_direction_between_neighbours() {
+        let board = Board::new(5, 5, 1, 1);
+        assert_eq!(board.direction_between((2, 2), (2, 1)), Some(Direction::Up));
+        assert_eq!(board.direction_between((0, 3), (4, 3)), Some(Direction::Left));
+        assert_eq!(board.direction_between((2, 2), (3, 3)), None);
+    }
+
     #[test]
     fn board_draws_level_correctly() {
         let mut board = Board::new(10, 10, 2, 2);
@@ -162,7 +510,7 @@ mod tests {
         const CELL_WIDTH: usize = 2;
         const CELL_HEIGHT: usize = 2;
         let mut board = Board::new(2, 2, CELL_WIDTH, CELL_HEIGHT);
-        board.set_cell(0, 0, GameObject::Snake);
+        board.set_cell(0, 0, GameObject::Snake(SnakePart::Head(Direction::Right)));
         board.set_cell(1, 0, GameObject::Food);
         board.set_cell(0, 1, GameObject::Wall);
         board.set_cell(1, 1, GameObject::Empty);
@@ -171,7 +519,7 @@ mod tests {
         board.render_to_buffer(&mut buffer);
 
         // Check colors in the buffer
-        let snake_color = Color::from(GameObject::Snake);
+        let snake_color = Color::from(GameObject::Snake(SnakePart::Head(Direction::Right)));
         let food_color = Color::from(GameObject::Food);
         let wall_color = Color::from(GameObject::Wall);
         let empty_color = Color::from(GameObject::Empty);
@@ -220,4 +568,123 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn board_paints_walls_with_tileset_sprites() {
+        // One pixel tiles, every tile coloured with its own index
+        let atlas: Vec<u8> = (0..26u8).flat_map(|tile| [tile, 0, 0, 255]).collect();
+        let mut board = Board::new(3, 3, 1, 1);
+        board.set_level_data(&b"   ##    ".to_vec());
+        board.set_tileset(Some(Tileset::new(atlas, 26, 1).unwrap()));
+        board.draw_level();
+        let mut buffer = vec![0; 9 * 4];
+        board.render_to_buffer(&mut buffer);
+
+        assert_eq!(buffer[12], 10 + 2);
+        assert_eq!(buffer[16], 10 + 8);
+        assert_eq!(buffer[20], 0);
+    }
+
+    #[test]
+    fn board_paints_eyes_facing_the_heading() {
+        let mut board = Board::new(1, 1, 10, 10);
+        board.set_cell(0, 0, GameObject::Snake(SnakePart::Head(Direction::Right)));
+        let mut buffer = vec![0; 10 * 10 * 4];
+        board.render_to_buffer(&mut buffer);
+
+        let green = |x: usize, y: usize| buffer[(y * 10 + x) * 4 + 1];
+        // Eyes are two pixels wide, a fifth of the cell away from the right edge
+        for (x, y) in [(6, 1), (7, 2), (6, 6), (7, 7)] {
+            assert_eq!(green(x, y), 0);
+        }
+        for (x, y) in [(2, 1), (6, 4), (9, 1)] {
+            assert_eq!(green(x, y), 255);
+        }
+    }
+
+    #[test]
+    fn board_paints_motions_until_the_next_render() {
+        let mut board = Board::new(2, 1, 4, 4);
+        board.set_smooth_movement(true);
+        let mut buffer = vec![0; 8 * 4 * 4];
+        board.add_motion(1, 0, Direction::Left, 0.5, GameObject::Food);
+        board.render_to_buffer(&mut buffer);
+
+        let red = |buffer: &[u8], x: usize| buffer[x * 4];
+        assert_eq!([4, 5, 6, 7].map(|x| red(&buffer, x)), [255, 255, 0, 0]);
+        assert_eq!(board.get_dirty_rects(), &[0, 0, 8, 4, 4, 0, 2, 4]);
+
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(red(&buffer, 4), 0);
+        assert_eq!(board.get_dirty_rects(), &[4, 0, 2, 4, 4, 0, 4, 4]);
+    }
+
+    #[test]
+    fn board_letterboxes_odd_screen_sizes() {
+        let mut board = Board::new(4, 2, 1, 1);
+        board.set_screen_size(11, 8);
+        // 2.75 pixels per cell, centred vertically
+        assert_eq!(board.cell_rect(0, 0), Some((0, 1, 3, 3)));
+        assert_eq!(board.cell_rect(1, 0), Some((3, 1, 3, 3)));
+        assert_eq!(board.cell_rect(3, 1), Some((8, 4, 3, 3)));
+
+        board.set_cell(1, 0, GameObject::Food);
+        let mut buffer = vec![7; 11 * 8 * 4];
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(&board.get_dirty_rects()[..4], &[0, 0, 11, 8]);
+        assert_eq!(&board.get_dirty_rects()[4..8], &[0, 1, 11, 3]);
+        let red = |x: usize, y: usize| buffer[(y * 11 + x) * 4];
+        assert_eq!([red(3, 0), red(3, 1), red(5, 3), red(6, 1), red(3, 7)], [0, 255, 255, 0, 0]);
+        assert!(buffer.chunks(4).all(|pixel| pixel[3] == 255));
+    }
+
+    #[test]
+    fn board_scrolls_when_cells_would_get_too_small() {
+        let mut board = Board::new(20, 10, 1, 1);
+        board.set_min_cell_size(4);
+        board.set_screen_size(18, 8);
+        // 4.5 columns and 2 rows of 4 pixels fit, the leftover pixels are letterboxed
+        assert_eq!(board.get_viewport().get_columns(), 4);
+        assert_eq!(board.cell_rect(3, 1), Some((13, 4, 4, 4)));
+        assert_eq!(board.cell_rect(4, 0), None);
+
+        board.set_cell(19, 0, GameObject::Food);
+        board.follow(0, 0);
+        assert_eq!(board.get_viewport().get_position(), (19, 9));
+        assert_eq!(board.cell_rect(19, 0), Some((1, 4, 4, 4)));
+        let mut buffer = vec![0; 18 * 8 * 4];
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(&buffer[(5 * 18 + 2) * 4..(5 * 18 + 2) * 4 + 3], &[255, 0, 0]);
+    }
+
+    #[test]
+    fn board_only_repaints_changed_cells() {
+        let mut board = Board::new(4, 3, 2, 2);
+        let mut buffer = vec![0; 4 * 3 * 2 * 2 * 4];
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(board.get_dirty_rects(), &[0, 0, 8, 2, 0, 2, 8, 2, 0, 4, 8, 2]);
+
+        board.render_to_buffer(&mut buffer);
+        assert!(board.get_dirty_rects().is_empty());
+
+        // Pixels of clean cells must not be touched
+        buffer.fill(7);
+        board.set_cell(1, 1, GameObject::Food);
+        board.set_cell(2, 1, GameObject::Food);
+        board.set_cell(0, 2, GameObject::Wall);
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(board.get_dirty_rects(), &[2, 2, 4, 2, 0, 4, 2, 2]);
+        assert_eq!(buffer[0], 7);
+        assert_eq!(buffer[(2 * 8 + 2) * 4], Color::from(GameObject::Food).r);
+    }
+
+    #[test]
+    fn board_repaints_everything_after_invalidate() {
+        let mut board = Board::new(3, 3, 1, 1);
+        let mut buffer = vec![0; 3 * 3 * 4];
+        board.render_to_buffer(&mut buffer);
+        board.invalidate();
+        board.render_to_buffer(&mut buffer);
+        assert_eq!(board.get_dirty_rects(), &[0, 0, 3, 1, 0, 1, 3, 1, 0, 2, 3, 1]);
+    }
 }
@@ -0,0 +1,148 @@
+use crate::Color;
+use crate::hud::{glyph, text_size, GLYPH_WIDTH};
+
+/// A pixel rectangle `(left, top, width, height)`.
+pub type Rect = (usize, usize, usize, usize);
+
+/// An RGBA pixel buffer to draw text and shapes into.
+pub struct Canvas<'a> {
+    buffer: &'a mut [u8],
+    width: usize,
+    height: usize,
+}
+
+impl<'a> Canvas<'a> {
+    pub fn new(buffer: &'a mut [u8], width: usize, height: usize) -> Self {
+        Canvas { buffer, width, height }
+    }
+
+    pub fn get_width(&self) -> usize {
+        self.width
+    }
+    pub fn get_height(&self) -> usize {
+        self.height
+    }
+
+    /// Draws `text` with its top left corner at `(left, top)`, every font pixel `scale` screen
+    /// pixels big. Letters are drawn in upper case, characters the font lacks as `?`. Returns
+    /// the rectangle the text covers, clipped to the canvas.
+    pub fn draw_text(&mut self, left: usize, top: usize, scale: usize, text: &str, color: Color) -> Rect {
+        for (index, char) in text.chars().enumerate() {
+            let glyph = glyph(char);
+            let glyph_left = left + index * (GLYPH_WIDTH + 1) * scale;
+            for (row, bits) in glyph.iter().enumerate() {
+                for column in 0..GLYPH_WIDTH {
+                    if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                        self.fill_rect((glyph_left + column * scale, top + row * scale, scale, scale), color);
+                    }
+                }
+            }
+        }
+        let (width, height) = text_size(text, scale);
+        self.clip((left, top, width, height))
+    }
+
+    /// Halves the brightness of a rectangle, so text on top of it stays readable.
+    pub fn darken(&mut self, rect: Rect) -> Rect {
+        let (left, top, width, height) = self.clip(rect);
+        for y in top..top + height {
+            for pixel in self.buffer[(y * self.width + left) * 4..(y * self.width + left + width) * 4].chunks_mut(4) {
+                pixel[0] /= 2;
+                pixel[1] /= 2;
+                pixel[2] /= 2;
+            }
+        }
+        (left, top, width, height)
+    }
+
+    /// Mixes `color` into a rectangle, `alpha` 0 keeps the pixels and 255 replaces them.
+    pub fn blend_rect(&mut self, rect: Rect, color: Color, alpha: u8) -> Rect {
+        let (left, top, width, height) = self.clip(rect);
+        let alpha = alpha as u32;
+        for y in top..top + height {
+            for pixel in self.buffer[(y * self.width + left) * 4..(y * self.width + left + width) * 4].chunks_mut(4) {
+                for (channel, value) in pixel.iter_mut().zip([color.r, color.g, color.b]) {
+                    *channel = ((value as u32 * alpha + *channel as u32 * (255 - alpha)) / 255) as u8;
+                }
+            }
+        }
+        (left, top, width, height)
+    }
+
+    /// Moves the whole picture by `(dx, dy)` pixels and fills the uncovered edges with `color`.
+    pub fn shift(&mut self, dx: isize, dy: isize, color: Color) {
+        let (width, height) = (self.width as isize, self.height as isize);
+        let rows: Vec<isize> = if dy > 0 { (0..height).rev().collect() } else { (0..height).collect() };
+        for y in rows {
+            let source_y = y - dy;
+            let row = (y * width * 4) as usize;
+            if source_y < 0 || source_y >= height || dx.abs() >= width {
+                self.buffer[row..row + self.width * 4].chunks_mut(4).for_each(|pixel| {
+                    pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+                });
+                continue;
+            }
+            let source_row = (source_y * width * 4) as usize;
+            let (source_x, target_x, count) = if dx >= 0 { (0, dx, width - dx) } else { (-dx, 0, width + dx) };
+            self.buffer.copy_within(
+                source_row + source_x as usize * 4..source_row + (source_x + count) as usize * 4,
+                row + target_x as usize * 4,
+            );
+            let edge = if dx >= 0 { 0..dx } else { width + dx..width };
+            for x in edge {
+                let index = row + x as usize * 4;
+                self.buffer[index..index + 4].copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+    }
+
+    /// Draws a one pixel wide frame just inside `rect`.
+    pub fn outline(&mut self, (left, top, width, height): Rect, color: Color) {
+        self.fill_rect((left, top, width, 1), color);
+        self.fill_rect((left, top + height.saturating_sub(1), width, 1), color);
+        self.fill_rect((left, top, 1, height), color);
+        self.fill_rect((left + width.saturating_sub(1), top, 1, height), color);
+    }
+
+    pub fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let (left, top, width, height) = self.clip(rect);
+        for y in top..top + height {
+            for pixel in self.buffer[(y * self.width + left) * 4..(y * self.width + left + width) * 4].chunks_mut(4) {
+                pixel.copy_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+    }
+
+    fn clip(&self, (left, top, width, height): Rect) -> Rect {
+        let left = left.min(self.width);
+        let top = top.min(self.height);
+        (left, top, width.min(self.width - left), height.min(self.height - top))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WHITE: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+    const BLACK: Color = Color { r: 0, g: 0, b: 0, a: 255 };
+
+    #[test]
+    fn canvas_blends_colors() {
+        let mut buffer = vec![100; 2 * 2 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 2, 2);
+        assert_eq!(canvas.blend_rect((1, 1, 5, 5), WHITE, 51), (1, 1, 1, 1));
+        assert_eq!(&buffer[12..15], &[131, 131, 131]);
+        assert_eq!(&buffer[0..3], &[100, 100, 100]);
+    }
+
+    #[test]
+    fn canvas_shifts_the_picture() {
+        // One byte per pixel is enough to follow them around
+        let mut buffer: Vec<u8> = (0..9u8).flat_map(|pixel| [pixel, 0, 0, 255]).collect();
+        let mut canvas = Canvas::new(&mut buffer, 3, 3);
+        canvas.shift(1, -1, BLACK);
+        let pixels: Vec<u8> = buffer.chunks(4).map(|pixel| pixel[0]).collect();
+        assert_eq!(pixels, [0, 3, 4, 0, 6, 7, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+use crate::autopilot::AutopilotController;
+use crate::board::Board;
+use crate::food::FoodManager;
+use crate::snake::Snake;
+use crate::{Direction, Key};
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const MAX_QUEUED_TURNS: usize = 3;
+
+/// Everything a controller may look at before its snake moves one cell.
+pub struct World<'a> {
+    pub board: &'a Board,
+    pub snakes: &'a [Snake],
+    pub foods: &'a FoodManager,
+    pub me: usize,
+}
+
+impl World<'_> {
+    pub fn snake(&self) -> &Snake {
+        &self.snakes[self.me]
+    }
+
+    /// Returns a grid that is `true` for every cell the snake must not move into.
+    pub fn blocked_cells(&self) -> Vec<bool> {
+        let width = self.board.get_width();
+        let height = self.board.get_height();
+        let mut blocked = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                blocked[self.board.xy_to_index(x, y)] = self.board.is_wall_at(x, y);
+            }
+        }
+        for snake in self.snakes {
+            for &(x, y) in snake.body() {
+                blocked[self.board.xy_to_index(x, y)] = true;
+            }
+        }
+        // The own tail moves out of the way unless the snake is growing
+        let snake = self.snake();
+        if snake.len() > 1 && snake.get_grow_pending() == 0 {
+            let (tail_x, tail_y) = snake.get_tail_pos();
+            blocked[self.board.xy_to_index(tail_x, tail_y)] = false;
+        }
+        blocked
+    }
+
+    /// Directions that don't reverse the snake and don't run straight into something.
+    pub fn safe_directions(&self, blocked: &[bool]) -> Vec<Direction> {
+        let snake = self.snake();
+        let (head_x, head_y) = snake.get_head_pos();
+        Direction::ALL
+            .into_iter()
+            .filter(|&direction| direction != snake.get_direction().opposite())
+            .filter(|&direction| {
+                let (x, y) = self.board.neighbour(head_x, head_y, direction);
+                !blocked[self.board.xy_to_index(x, y)]
+            })
+            .collect()
+    }
+}
+
+/// Steers a snake. The game asks the controller for a direction every time the snake is about
+/// to move one cell, so players, bots and replays all drive the snake the same way.
+pub trait SnakeController {
+    fn next_direction(&mut self, world: &World) -> Option<Direction>;
+
+    fn key_down(&mut self, _key: Key) {}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[wasm_bindgen]
+pub enum ControllerKind {
+    Keyboard,
+    Greedy,
+    Survival,
+    Autopilot,
+}
+
+impl ControllerKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ControllerKind::Keyboard => "keyboard",
+            ControllerKind::Greedy => "greedy",
+            ControllerKind::Survival => "survival",
+            ControllerKind::Autopilot => "autopilot",
+        }
+    }
+
+    pub fn create(self) -> Box<dyn SnakeController> {
+        match self {
+            ControllerKind::Keyboard => Box::new(KeyboardController::new()),
+            ControllerKind::Greedy => Box::new(GreedyController),
+            ControllerKind::Survival => Box::new(SurvivalController),
+            ControllerKind::Autopilot => Box::new(AutopilotController::new()),
+        }
+    }
+}
+
+impl FromStr for ControllerKind {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "keyboard" => Ok(ControllerKind::Keyboard),
+            "greedy" => Ok(ControllerKind::Greedy),
+            "survival" => Ok(ControllerKind::Survival),
+            "autopilot" => Ok(ControllerKind::Autopilot),
+            _ => Err(format!("Unknown controller: {value}")),
+        }
+    }
+}
+
+/// Applies the arrow keys pressed by the player, one turn per cell so quick double turns
+/// aren't lost. Relative turns are taken from the heading the snake has when it gets to them.
+pub struct KeyboardController {
+    turns: VecDeque<Key>,
+}
+
+impl KeyboardController {
+    pub fn new() -> Self {
+        KeyboardController {
+            turns: VecDeque::new(),
+        }
+    }
+}
+
+impl SnakeController for KeyboardController {
+    fn next_direction(&mut self, world: &World) -> Option<Direction> {
+        let heading = world.snake().get_direction();
+        let direction = match self.turns.pop_front()? {
+            Key::TurnLeft => heading.turn_left(),
+            Key::TurnRight => heading.turn_right(),
+            key => key.into(),
+        };
+        Some(direction)
+    }
+
+    fn key_down(&mut self, key: Key) {
+        if key.steers() && self.turns.len() < MAX_QUEUED_TURNS {
+            self.turns.push_back(key);
+        }
+    }
+}
+
+/// Takes the shortest path to the nearest food. Falls back to the roomiest move when no food
+/// can be reached.
+pub struct GreedyController;
+
+impl SnakeController for GreedyController {
+    fn next_direction(&mut self, world: &World) -> Option<Direction> {
+        let blocked = world.blocked_cells();
+        let (head_x, head_y) = world.snake().get_head_pos();
+        first_step_to_food(world, &blocked, head_x, head_y)
+            .or_else(|| roomiest_direction(world, &blocked))
+    }
+}
+
+/// Only heads for food along moves that leave enough room for the whole snake, and otherwise
+/// picks the move with the most reachable space.
+pub struct SurvivalController;
+
+impl SnakeController for SurvivalController {
+    fn next_direction(&mut self, world: &World) -> Option<Direction> {
+        let blocked = world.blocked_cells();
+        let (head_x, head_y) = world.snake().get_head_pos();
+        let length = world.snake().len();
+
+        let roomy_moves = world.safe_directions(&blocked).into_iter().filter_map(|direction| {
+            let (x, y) = world.board.neighbour(head_x, head_y, direction);
+            (flood_fill(world.board, &blocked, x, y) >= length).then_some((direction, x, y))
+        });
+        roomy_moves
+            .min_by_key(|&(_, x, y)| distance_to_food(world, &blocked, x, y).unwrap_or(usize::MAX))
+            .map(|(direction, _, _)| direction)
+            .or_else(|| roomiest_direction(world, &blocked))
+    }
+}
+
+/// Breadth-first search from the head to the nearest food, returning the first move of the path.
+fn first_step_to_food(world: &World, blocked: &[bool], head_x: usize, head_y: usize) -> Option<Direction> {
+    let board = world.board;
+    let mut visited = blocked.to_vec();
+    let mut queue = VecDeque::new();
+    for direction in world.safe_directions(blocked) {
+        let (x, y) = board.neighbour(head_x, head_y, direction);
+        visited[board.xy_to_index(x, y)] = true;
+        queue.push_back((x, y, direction));
+    }
+
+    while let Some((x, y, first_direction)) = queue.pop_front() {
+        if world.foods.is_food_at(x, y) {
+            return Some(first_direction);
+        }
+        for direction in Direction::ALL {
+            let (next_x, next_y) = board.neighbour(x, y, direction);
+            let idx = board.xy_to_index(next_x, next_y);
+            if !visited[idx] {
+                visited[idx] = true;
+                queue.push_back((next_x, next_y, first_direction));
+            }
+        }
+    }
+    None
+}
+
+fn distance_to_food(world: &World, blocked: &[bool], start_x: usize, start_y: usize) -> Option<usize> {
+    let board = world.board;
+    let mut visited = blocked.to_vec();
+    let mut queue = VecDeque::from([(start_x, start_y, 0)]);
+    visited[board.xy_to_index(start_x, start_y)] = true;
+
+    while let Some((x, y, distance)) = queue.pop_front() {
+        if world.foods.is_food_at(x, y) {
+            return Some(distance);
+        }
+        for direction in Direction::ALL {
+            let (next_x, next_y) = board.neighbour(x, y, direction);
+            let idx = board.xy_to_index(next_x, next_y);
+            if !visited[idx] {
+                visited[idx] = true;
+                queue.push_back((next_x, next_y, distance + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Counts the free cells reachable from `(start_x, start_y)`.
+pub fn flood_fill(board: &Board, blocked: &[bool], start_x: usize, start_y: usize) -> usize {
+    let mut visited = blocked.to_vec();
+    let mut stack = vec![(start_x, start_y)];
+    visited[board.xy_to_index(start_x, start_y)] = true;
+    let mut count = 0;
+
+    while let Some((x, y)) = stack.pop() {
+        count += 1;
+        for direction in Direction::ALL {
+            let (next_x, next_y) = board.neighbour(x, y, direction);
+            let idx = board.xy_to_index(next_x, next_y);
+            if !visited[idx] {
+                visited[idx] = true;
+                stack.push((next_x, next_y));
+            }
+        }
+    }
+    count
+}
+
+fn roomiest_direction(world: &World, blocked: &[bool]) -> Option<Direction> {
+    let (head_x, head_y) = world.snake().get_head_pos();
+    world.safe_directions(blocked).into_iter().max_by_key(|&direction| {
+        let (x, y) = world.board.neighbour(head_x, head_y, direction);
+        flood_fill(world.board, blocked, x, y)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board_with_level(width: usize, height: usize, level_data: &[u8]) -> Board {
+        let mut board = Board::new(width, height, 1, 1);
+        board.set_level_data(&level_data.to_vec());
+        board
+    }
+
+    #[test]
+    fn keyboard_controller_queues_turns() {
+        let board = Board::new(5, 5, 1, 1);
+        let snakes = [Snake::new(2, 2)];
+        let foods = FoodManager::new();
+        let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+
+        let mut controller = KeyboardController::new();
+        controller.key_down(Key::ArrowUp);
+        controller.key_down(Key::Space);
+        controller.key_down(Key::ArrowLeft);
+        assert_eq!(controller.next_direction(&world), Some(Direction::Up));
+        assert_eq!(controller.next_direction(&world), Some(Direction::Left));
+        assert_eq!(controller.next_direction(&world), None);
+    }
+
+    #[test]
+    fn keyboard_controller_turns_relative_to_the_heading() {
+        let board = Board::new(5, 5, 1, 1);
+        let mut snakes = [Snake::new(2, 2)];
+        let foods = FoodManager::new();
+
+        let mut controller = KeyboardController::new();
+        controller.key_down(Key::TurnLeft);
+        controller.key_down(Key::TurnLeft);
+        controller.key_down(Key::TurnRight);
+        let mut headings = Vec::new();
+        for _ in 0..3 {
+            let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+            let direction = controller.next_direction(&world).unwrap();
+            snakes[0].set_direction(direction);
+            headings.push(direction);
+        }
+        assert_eq!(headings, [Direction::Up, Direction::Left, Direction::Up]);
+    }
+
+    #[test]
+    fn greedy_controller_walks_towards_food() {
+        let board = Board::new(10, 10, 1, 1);
+        let snakes = [Snake::new(2, 2)];
+        let mut foods = FoodManager::new();
+        foods.add_food(2, 5);
+        let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+
+        assert_eq!(GreedyController.next_direction(&world), Some(Direction::Down));
+    }
+
+    #[test]
+    fn greedy_controller_goes_around_walls() {
+        let board = board_with_level(5, 5, b"###### # ##   ##   ######");
+        let snakes = [Snake::new(1, 1)];
+        let mut foods = FoodManager::new();
+        foods.add_food(3, 1);
+        let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+
+        assert_eq!(GreedyController.next_direction(&world), Some(Direction::Down));
+    }
+
+    #[test]
+    fn survival_controller_avoids_dead_ends() {
+        // The food sits in a pocket of a single cell, entering it would trap the snake.
+        let board = board_with_level(7, 5, b"######### #####     ##     ########");
+        let mut snake = Snake::new(1, 3);
+        snake.grow(2);
+        snake.step(&board);
+        snake.set_direction(Direction::Up);
+        snake.step(&board);
+        let snakes = [snake];
+        let mut foods = FoodManager::new();
+        foods.add_food(2, 1);
+        let world = World { board: &board, snakes: &snakes, foods: &foods, me: 0 };
+
+        assert_eq!(GreedyController.next_direction(&world), Some(Direction::Up));
+        assert_eq!(SurvivalController.next_direction(&world), Some(Direction::Right));
+    }
+
+    #[test]
+    fn flood_fill_counts_reachable_cells() {
+        let board = board_with_level(7, 5, b"######### #####     ##     ########");
+        let blocked: Vec<bool> = (0..35).map(|idx| board.is_wall_at(idx % 7, idx / 7)).collect();
+        assert_eq!(flood_fill(&board, &blocked, 1, 2), 11);
+    }
+}
@@ -0,0 +1,226 @@
+use crate::game::GameWasm;
+use crate::snake::MAX_SPEED;
+use crate::{Direction, Event, GameObject, GameState};
+
+/// Grid channels in observation order: walls, snake bodies, the player's head and food.
+pub const CHANNELS: usize = 4;
+pub const FEATURES: usize = 15;
+
+const DEATH_REWARD: f32 = -1.0;
+/// Pixel size of the game's screen. Observations are made from the board cells, not the screen.
+const SCREEN_SIZE: usize = 100;
+
+/// What an agent sees of the game after each step.
+pub struct Observation {
+    pub width: usize,
+    pub height: usize,
+    /// `CHANNELS x height x width` cells set to 1 where the channel's object is, 0 elsewhere.
+    pub grid: Vec<u8>,
+    /// Hand-made features: danger ahead/left/right, heading (up, down, left, right), nearest food
+    /// above/below/left/right, normalized offset to the nearest food, length and speed.
+    pub features: Vec<f32>,
+}
+
+/// A gym-style environment around the real game rules and levels. Each step moves the player's
+/// snake exactly one cell, computer snakes move along with it at their own speed.
+pub struct SnakeEnv {
+    game: GameWasm,
+}
+
+impl SnakeEnv {
+    /// Creates an environment on one of the built-in levels, or `None` if there is no such level.
+    pub fn new(level_name: &str) -> Option<Self> {
+        let mut game = GameWasm::new(SCREEN_SIZE, SCREEN_SIZE);
+        if !game.get_level_names().iter().any(|name| name == level_name) {
+            return None;
+        }
+        // Episodes are played by an agent, not by the player
+        game.set_keeps_records(false);
+        game.load_level(level_name);
+        Some(SnakeEnv { game })
+    }
+
+    pub fn game(&self) -> &GameWasm {
+        &self.game
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        self.game.reset_with_seed(seed);
+        self.observe()
+    }
+
+    /// Turns the snake towards `action` (reversing is ignored) and moves it one cell. Returns the
+    /// new observation, the reward (1 per food, -1 for dying) and whether the episode is over.
+    pub fn step(&mut self, action: Direction) -> (Observation, f32, bool) {
+        if self.game.get_game_state() != GameState::Running {
+            return (self.observe(), 0.0, true);
+        }
+
+        self.game.steer_player(action);
+        self.game.take_events();
+        let moves = self.game.get_player().get_moves();
+        while self.game.get_game_state() == GameState::Running
+            && self.game.get_player().get_moves() == moves
+        {
+            self.game.tick();
+        }
+
+        let done = self.game.get_game_state() == GameState::GameOver;
+        let reward = if done {
+            DEATH_REWARD
+        } else {
+            let events = self.game.take_events();
+            events.iter().filter(|event| matches!(event, Event::EatFood { .. })).count() as f32
+        };
+        (self.observe(), reward, done)
+    }
+
+    fn observe(&mut self) -> Observation {
+        self.game.draw_board();
+        let board = self.game.get_board();
+        let width = board.get_width();
+        let height = board.get_height();
+        let plane = width * height;
+
+        let mut grid = vec![0; CHANNELS * plane];
+        for y in 0..height {
+            for x in 0..width {
+                let idx = board.xy_to_index(x, y);
+                match board.get_cell(x, y) {
+                    Some(GameObject::Wall) => grid[idx] = 1,
+                    Some(GameObject::Snake(_) | GameObject::Opponent(_)) => grid[plane + idx] = 1,
+                    Some(GameObject::Food) => grid[3 * plane + idx] = 1,
+                    _ => {}
+                }
+            }
+        }
+        let (head_x, head_y) = self.game.get_player().get_head_pos();
+        grid[2 * plane + board.xy_to_index(head_x, head_y)] = 1;
+
+        Observation {
+            width,
+            height,
+            grid,
+            features: self.features(),
+        }
+    }
+
+    fn features(&self) -> Vec<f32> {
+        let board = self.game.get_board();
+        let snake = self.game.get_player();
+        let (head_x, head_y) = snake.get_head_pos();
+        let heading = snake.get_direction();
+        let mut features = Vec::with_capacity(FEATURES);
+
+        for direction in [heading, heading.turn_left(), heading.turn_right()] {
+            let (x, y) = board.neighbour(head_x, head_y, direction);
+            let blocked = matches!(
+                board.get_cell(x, y),
+                Some(GameObject::Wall | GameObject::Snake(_) | GameObject::Opponent(_))
+            );
+            features.push(blocked as u8 as f32);
+        }
+        features.extend(Direction::ALL.map(|direction| (direction == heading) as u8 as f32));
+
+        // Offsets take the shortest way, which may be around the edge of the board
+        let offset = |from: usize, to: usize, size: usize| {
+            let forward = (to + size - from) % size;
+            if forward <= size / 2 { forward as f32 } else { forward as f32 - size as f32 }
+        };
+        let nearest_food = self
+            .game
+            .get_food_manager()
+            .food_positions()
+            .map(|(x, y)| (offset(head_x, x, board.get_width()), offset(head_y, y, board.get_height())))
+            .min_by(|a, b| (a.0.abs() + a.1.abs()).total_cmp(&(b.0.abs() + b.1.abs())));
+        let (dx, dy) = nearest_food.unwrap_or((0.0, 0.0));
+        features.extend([dy < 0.0, dy > 0.0, dx < 0.0, dx > 0.0].map(|flag| flag as u8 as f32));
+        features.push(dx / (board.get_width() / 2) as f32);
+        features.push(dy / (board.get_height() / 2) as f32);
+
+        features.push(snake.len() as f32 / (board.get_width() * board.get_height()) as f32);
+        features.push(snake.get_speed() / MAX_SPEED);
+        features
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::LifetimeStats;
+
+    #[test]
+    fn env_rejects_unknown_levels() {
+        assert!(SnakeEnv::new("Board 1").is_some());
+        assert!(SnakeEnv::new("nonexistent_level").is_none());
+    }
+
+    #[test]
+    fn env_observation_has_expected_shape() {
+        let mut env = SnakeEnv::new("Board 1").unwrap();
+        let observation = env.reset(1);
+        let plane = observation.width * observation.height;
+        assert_eq!(observation.grid.len(), CHANNELS * plane);
+        assert_eq!(observation.features.len(), FEATURES);
+
+        // Walls surround Board 1 and the snake starts in the middle heading right
+        assert_eq!(observation.grid[0], 1);
+        assert_eq!(observation.grid[2 * plane + 50 + 50 * observation.width], 1);
+        assert_eq!(observation.grid[3 * plane..].iter().filter(|&&cell| cell == 1).count(), 1);
+        assert_eq!(observation.features[3..7], [0.0, 0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn env_step_moves_one_cell() {
+        let mut env = SnakeEnv::new("Board 1").unwrap();
+        env.reset(1);
+        let (observation, reward, done) = env.step(Direction::Up);
+        assert!(!done);
+        assert_eq!(reward, 0.0);
+        assert_eq!(env.game().get_player().get_head_pos(), (50, 49));
+        assert_eq!(observation.features[3..7], [1.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn env_ends_episode_when_snake_dies() {
+        let mut env = SnakeEnv::new("Board 1").unwrap();
+        env.reset(1);
+        let mut steps = 0;
+        loop {
+            let (_, reward, done) = env.step(Direction::Up);
+            steps += 1;
+            if done {
+                assert_eq!(reward, DEATH_REWARD);
+                break;
+            }
+        }
+        assert!(steps <= 50);
+        assert!(env.step(Direction::Up).2);
+    }
+
+    #[test]
+    fn env_episodes_keep_out_of_the_player_records() {
+        let mut env = SnakeEnv::new("Board 1").unwrap();
+        env.reset(1);
+        while !env.step(Direction::Up).2 {}
+        env.reset(2);
+        let game = env.game();
+        assert_eq!(game.get_lifetime_stats(), LifetimeStats::new());
+        assert!(game.get_achievements().iter().all(|info| !info.unlocked));
+    }
+
+    #[test]
+    fn env_is_deterministic_for_a_seed() {
+        let actions = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+        let mut first = SnakeEnv::new("Board 2").unwrap();
+        let mut second = SnakeEnv::new("Board 2").unwrap();
+        assert_eq!(first.reset(9).grid, second.reset(9).grid);
+        for step in 0..200 {
+            let action = actions[(step / 7) % actions.len()];
+            let (first_observation, first_reward, _) = first.step(action);
+            let (second_observation, second_reward, _) = second.step(action);
+            assert_eq!(first_observation.grid, second_observation.grid);
+            assert_eq!(first_reward, second_reward);
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::collections::VecDeque;
+use crate::snake::DeathCause;
+use crate::GameEvent;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+/// Events nobody polls are dropped, oldest first, once this many are waiting.
+const MAX_QUEUED_EVENTS: usize = 256;
+
+/// Something that happened in the game, with the details hosts need to react to it.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// The player ate the food at `(x, y)` and scored `points` for it.
+    EatFood { x: usize, y: usize, points: u32 },
+    /// The player's snake died with its head at `(x, y)`.
+    GameOver { x: usize, y: usize, score: u32, cause: DeathCause },
+    GameStart,
+    GamePause,
+    /// A computer snake died with its head at `(x, y)`.
+    OpponentDied { x: usize, y: usize, cause: DeathCause },
+    /// A level was put onto the board, the game waits for the player to start.
+    LevelLoaded { name: String, width: usize, height: usize },
+    /// The player's snake now moves `speed` cells per second.
+    SpeedChanged { speed: f32 },
+    /// The player unlocked the achievement with this id and name.
+    AchievementUnlocked { id: String, name: String },
+    /// The player pressed the menu key, the game is paused until the host resumes it.
+    MenuRequested,
+}
+
+impl Event {
+    pub fn kind(&self) -> GameEvent {
+        match self {
+            Event::EatFood { .. } => GameEvent::EatFood,
+            Event::GameOver { .. } => GameEvent::GameOver,
+            Event::GameStart => GameEvent::GameStart,
+            Event::GamePause => GameEvent::GamePause,
+            Event::OpponentDied { .. } => GameEvent::OpponentDied,
+            Event::LevelLoaded { .. } => GameEvent::LevelLoaded,
+            Event::SpeedChanged { .. } => GameEvent::SpeedChanged,
+            Event::AchievementUnlocked { .. } => GameEvent::AchievementUnlocked,
+            Event::MenuRequested => GameEvent::MenuRequested,
+        }
+    }
+}
+
+/// An event as JavaScript sees it. Fields an event doesn't have are 0 or empty. Deaths carry
+/// the name of their cause, see `DeathCause::name`, and the wall or snake cell that was hit in
+/// `hit_x` and `hit_y`, or how far behind the head a snake bit itself in `segment`. Unlocked
+/// achievements carry their id and name, see `GameWasm::get_achievements`.
+#[derive(Clone, Debug, PartialEq)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct GameEventInfo {
+    pub kind: GameEvent,
+    pub x: usize,
+    pub y: usize,
+    pub points: u32,
+    pub score: u32,
+    pub speed: f32,
+    pub level: String,
+    pub width: usize,
+    pub height: usize,
+    pub cause: String,
+    pub hit_x: usize,
+    pub hit_y: usize,
+    pub segment: usize,
+    pub achievement: String,
+    pub achievement_name: String,
+}
+
+impl From<Event> for GameEventInfo {
+    fn from(event: Event) -> Self {
+        let mut info = GameEventInfo {
+            kind: event.kind(),
+            x: 0,
+            y: 0,
+            points: 0,
+            score: 0,
+            speed: 0.0,
+            level: String::new(),
+            width: 0,
+            height: 0,
+            cause: String::new(),
+            hit_x: 0,
+            hit_y: 0,
+            segment: 0,
+            achievement: String::new(),
+            achievement_name: String::new(),
+        };
+        match event {
+            Event::EatFood { x, y, points } => (info.x, info.y, info.points) = (x, y, points),
+            Event::GameOver { x, y, score, cause } => {
+                (info.x, info.y, info.score) = (x, y, score);
+                info.set_cause(cause);
+            }
+            Event::OpponentDied { x, y, cause } => {
+                (info.x, info.y) = (x, y);
+                info.set_cause(cause);
+            }
+            Event::LevelLoaded { name, width, height } => (info.level, info.width, info.height) = (name, width, height),
+            Event::SpeedChanged { speed } => info.speed = speed,
+            Event::AchievementUnlocked { id, name } => (info.achievement, info.achievement_name) = (id, name),
+            Event::GameStart | Event::GamePause | Event::MenuRequested => {}
+        }
+        info
+    }
+}
+
+impl GameEventInfo {
+    fn set_cause(&mut self, cause: DeathCause) {
+        self.cause = cause.name().to_string();
+        match cause {
+            DeathCause::HitWall { x, y } | DeathCause::HitOtherSnake { x, y } => (self.hit_x, self.hit_y) = (x, y),
+            DeathCause::HitOwnBody { segment } => self.segment = segment,
+        }
+    }
+}
+
+/// Collects events while the game runs until the host takes them, so no host code runs in the
+/// middle of an update.
+pub struct EventQueue {
+    events: VecDeque<Event>,
+}
+
+impl EventQueue {
+    pub fn new() -> Self {
+        EventQueue { events: VecDeque::new() }
+    }
+
+    pub fn push(&mut self, event: Event) {
+        if self.events.len() == MAX_QUEUED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event);
+    }
+
+    /// Takes all waiting events, oldest first.
+    pub fn drain(&mut self) -> Vec<Event> {
+        self.events.drain(..).collect()
+    }
+}
+
+/// Native code that wants to hear about events as they happen, e.g. stats or achievements.
+/// Closures taking an `&Event` are observers too.
+pub trait EventObserver {
+    fn on_event(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event)> EventObserver for F {
+    fn on_event(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+struct Subscription {
+    handle: u32,
+    kinds: Vec<GameEvent>,
+    observer: Box<dyn EventObserver>,
+}
+
+/// Observers by subscription handle, each with the kinds of events it wants.
+pub struct EventObservers {
+    next_handle: u32,
+    subscriptions: Vec<Subscription>,
+}
+
+impl EventObservers {
+    pub fn new() -> Self {
+        EventObservers { next_handle: 1, subscriptions: Vec::new() }
+    }
+
+    /// Adds an observer for the given kinds of events, or for all events if `kinds` is empty.
+    /// Returns the handle to unsubscribe it with.
+    pub fn subscribe(&mut self, kinds: &[GameEvent], observer: Box<dyn EventObserver>) -> u32 {
+        let handle = self.next_handle;
+        self.next_handle += 1;
+        self.subscriptions.push(Subscription { handle, kinds: kinds.to_vec(), observer });
+        handle
+    }
+
+    /// Removes an observer. Returns `false` if the handle wasn't subscribed.
+    pub fn unsubscribe(&mut self, handle: u32) -> bool {
+        let count = self.subscriptions.len();
+        self.subscriptions.retain(|subscription| subscription.handle != handle);
+        self.subscriptions.len() != count
+    }
+
+    /// Tells every observer that wants this kind of event about it, in the order they subscribed.
+    pub fn notify(&mut self, event: &Event) {
+        let kind = event.kind();
+        for subscription in &mut self.subscriptions {
+            if subscription.kinds.is_empty() || subscription.kinds.contains(&kind) {
+                subscription.observer.on_event(event);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn event_queue_drops_the_oldest_events() {
+        let mut queue = EventQueue::new();
+        for x in 0..MAX_QUEUED_EVENTS + 2 {
+            queue.push(Event::OpponentDied { x, y: 0, cause: DeathCause::HitOwnBody { segment: 1 } });
+        }
+        let events = queue.drain();
+        assert_eq!(events.len(), MAX_QUEUED_EVENTS);
+        assert_eq!(events[0], Event::OpponentDied { x: 2, y: 0, cause: DeathCause::HitOwnBody { segment: 1 } });
+        assert!(queue.drain().is_empty());
+    }
+
+    #[test]
+    fn event_observers_filter_by_kind() {
+        let all = Rc::new(RefCell::new(Vec::new()));
+        let pauses = Rc::new(RefCell::new(0));
+        let mut observers = EventObservers::new();
+        let recorder = all.clone();
+        let all_handle = observers.subscribe(&[], Box::new(move |event: &Event| recorder.borrow_mut().push(event.clone())));
+        let counter = pauses.clone();
+        observers.subscribe(&[GameEvent::GamePause], Box::new(move |_: &Event| *counter.borrow_mut() += 1));
+
+        observers.notify(&Event::GameStart);
+        observers.notify(&Event::GamePause);
+        assert!(observers.unsubscribe(all_handle));
+        assert!(!observers.unsubscribe(all_handle));
+        observers.notify(&Event::GamePause);
+        assert_eq!(*all.borrow(), [Event::GameStart, Event::GamePause]);
+        assert_eq!(*pauses.borrow(), 2);
+    }
+
+    #[test]
+    fn event_info_carries_the_payload() {
+        let info = GameEventInfo::from(Event::LevelLoaded { name: "Board 2".to_string(), width: 100, height: 80 });
+        assert!(info.kind == GameEvent::LevelLoaded);
+        assert_eq!((info.level.as_str(), info.width, info.height, info.x), ("Board 2", 100, 80, 0));
+    }
+}
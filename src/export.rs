@@ -0,0 +1,110 @@
+//! Writes rendered frames to image files, for level thumbnails and clips of bug reports. Only
+//! built for native targets, browsers can take screenshots of the canvas themselves.
+
+use crate::board::Board;
+use crate::level::Result;
+
+/// GIF frame delays are counted in hundredths of a second.
+const GIF_DELAY_UNIT_MILLIS: f32 = 10.0;
+/// How hard the GIF encoder tries to find good colours when a frame has more than 256, from 1
+/// (best) to 30 (fastest).
+const GIF_QUANTIZER_SPEED: i32 = 10;
+
+/// Encodes an RGBA pixel buffer as PNG.
+pub fn encode_png(rgba: &[u8], width: usize, height: usize) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut encoder = png::Encoder::new(&mut bytes, width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(bytes)
+}
+
+/// Renders every cell of a board into a new RGBA buffer of the board's screen size.
+pub(crate) fn render_board(board: &mut Board) -> Vec<u8> {
+    let mut buffer = vec![0; board.get_screen_width() * board.get_screen_height() * 4];
+    board.invalidate();
+    board.render_to_buffer(&mut buffer);
+    buffer
+}
+
+/// Collects frames of the same size and encodes them as an endlessly looping GIF.
+pub struct GifRecorder {
+    width: usize,
+    height: usize,
+    delay: u16,
+    frames: Vec<Vec<u8>>,
+}
+
+impl GifRecorder {
+    /// Frames are `frame_millis` apart, rounded to the 10 ms steps GIFs can store.
+    pub fn new(width: usize, height: usize, frame_millis: f32) -> Self {
+        GifRecorder {
+            width,
+            height,
+            delay: (frame_millis / GIF_DELAY_UNIT_MILLIS).round().max(1.0) as u16,
+            frames: Vec::new(),
+        }
+    }
+
+    pub fn add_frame(&mut self, rgba: &[u8]) {
+        self.frames.push(rgba.to_vec());
+    }
+
+    pub fn finish(self) -> Result<Vec<u8>> {
+        let (width, height) = (u16::try_from(self.width)?, u16::try_from(self.height)?);
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, width, height, &[])?;
+            encoder.set_repeat(gif::Repeat::Infinite)?;
+            for mut rgba in self.frames {
+                let mut frame = gif::Frame::from_rgba_speed(width, height, &mut rgba, GIF_QUANTIZER_SPEED);
+                frame.delay = self.delay;
+                encoder.write_frame(&frame)?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameObject;
+
+    #[test]
+    fn export_writes_a_png_of_a_board() {
+        let mut board = Board::new(4, 4, 2, 2);
+        board.set_cell(1, 1, GameObject::Food);
+        let rgba = render_board(&mut board);
+        let png = encode_png(&rgba, 8, 8).unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let mut reader = png::Decoder::new(std::io::Cursor::new(png)).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, rgba);
+        assert_eq!(&decoded[(2 * 8 + 2) * 4..(2 * 8 + 2) * 4 + 4], &[255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn export_writes_an_animated_gif() {
+        let mut recorder = GifRecorder::new(2, 1, 100.0);
+        recorder.add_frame(&[255, 0, 0, 255, 0, 0, 0, 255]);
+        recorder.add_frame(&[0, 0, 0, 255, 255, 0, 0, 255]);
+        let gif = recorder.finish().unwrap();
+        assert_eq!(&gif[..6], b"GIF89a");
+
+        let mut options = gif::DecodeOptions::new();
+        options.set_color_output(gif::ColorOutput::RGBA);
+        let mut decoder = options.read_info(gif.as_slice()).unwrap();
+        let first = decoder.read_next_frame().unwrap().unwrap();
+        assert_eq!(first.delay, 10);
+        assert_eq!(&first.buffer[..4], &[255, 0, 0, 255]);
+        let second = decoder.read_next_frame().unwrap().unwrap();
+        assert_eq!(&second.buffer[4..8], &[255, 0, 0, 255]);
+        assert!(decoder.read_next_frame().unwrap().is_none());
+    }
+}
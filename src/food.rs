@@ -13,16 +13,27 @@ impl Food {
     }
 }
 
+const RANDOM_SPAWN_ATTEMPTS: usize = 100;
+
 pub struct FoodManager {
     foods: Vec<Food>,
+    spawn_area: Option<Vec<bool>>,
 }
 
 impl FoodManager {
     pub fn new() -> Self {
-        FoodManager { foods: Vec::new() }
+        FoodManager {
+            foods: Vec::new(),
+            spawn_area: None,
+        }
+    }
+
+    /// Restricts food to the cells marked `true`, indexed like the board cells.
+    pub fn set_spawn_area(&mut self, spawn_area: Option<Vec<bool>>) {
+        self.spawn_area = spawn_area;
     }
 
-    fn add_food(&mut self, x: usize, y: usize) {
+    pub fn add_food(&mut self, x: usize, y: usize) {
         self.foods.push(Food::new(x, y));
     }
 
@@ -34,21 +45,49 @@ impl FoodManager {
         self.foods.iter().any(|food| food.x == x && food.y == y)
     }
 
+    pub fn food_positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.foods.iter().map(|food| (food.x, food.y))
+    }
+
     pub fn render_foods_to_board(&self, board: &mut Board) {
         for food in &self.foods {
             board.set_cell(food.x, food.y, GameObject::Food);
         }
     }
 
-    pub fn spawn_food(&mut self, board: &Board, snake: &Snake) {
-        loop {
-            let x = fastrand::usize(0..board.get_width());
-            let y = fastrand::usize(0..board.get_height());
-            if !board.is_wall_at(x, y) && !snake.is_snake_at(x, y) && !self.is_food_at(x, y) {
+    fn can_spawn_at(&self, board: &Board, snakes: &[Snake], x: usize, y: usize) -> bool {
+        let in_spawn_area = self
+            .spawn_area
+            .as_ref()
+            .is_none_or(|area| area[board.xy_to_index(x, y)]);
+        in_spawn_area
+            && !board.is_wall_at(x, y)
+            && !snakes.iter().any(|snake| snake.is_snake_at(x, y))
+            && !self.is_food_at(x, y)
+    }
+
+    /// Places a food on a random free cell. Returns `false` if the board is full.
+    pub fn spawn_food(&mut self, board: &Board, snakes: &[Snake], rng: &mut fastrand::Rng) -> bool {
+        for _ in 0..RANDOM_SPAWN_ATTEMPTS {
+            let x = rng.usize(0..board.get_width());
+            let y = rng.usize(0..board.get_height());
+            if self.can_spawn_at(board, snakes, x, y) {
                 self.add_food(x, y);
-                break;
+                return true;
             }
         }
+
+        // Nearly full board, random guessing would take forever
+        let free_cells: Vec<(usize, usize)> = (0..board.get_height())
+            .flat_map(|y| (0..board.get_width()).map(move |x| (x, y)))
+            .filter(|&(x, y)| self.can_spawn_at(board, snakes, x, y))
+            .collect();
+        if free_cells.is_empty() {
+            return false;
+        }
+        let (x, y) = free_cells[rng.usize(0..free_cells.len())];
+        self.add_food(x, y);
+        true
     }
 }
 
@@ -98,16 +137,59 @@ mod tests {
         board.set_level_data(&level_data);
         let mut snake = Snake::new(1, 1);
         snake.grow(5);
+        let snakes = [snake];
 
+        let mut rng = fastrand::Rng::with_seed(7);
         let mut manager = FoodManager::new();
         for i in 0..10 {
-            manager.spawn_food(&board, &snake);
+            manager.spawn_food(&board, &snakes, &mut rng);
             let food = &manager.foods[i];
             assert!(!board.is_wall_at(food.x, food.y));
-            assert!(!snake.is_snake_at(food.x, food.y));
+            assert!(!snakes[0].is_snake_at(food.x, food.y));
         }
         assert_eq!(manager.foods.len(), 10);
     }
-}
 
+    #[test]
+    fn food_manager_spawns_food_only_inside_spawn_area() {
+        let board = Board::new(4, 4, 1, 1);
+        let mut spawn_area = vec![false; 16];
+        spawn_area[board.xy_to_index(1, 2)] = true;
+        spawn_area[board.xy_to_index(3, 3)] = true;
 
+        let mut rng = fastrand::Rng::with_seed(7);
+        let mut manager = FoodManager::new();
+        manager.set_spawn_area(Some(spawn_area));
+        assert!(manager.spawn_food(&board, &[], &mut rng));
+        assert!(manager.spawn_food(&board, &[], &mut rng));
+        assert!(!manager.spawn_food(&board, &[], &mut rng));
+        assert!(manager.is_food_at(1, 2));
+        assert!(manager.is_food_at(3, 3));
+    }
+
+    #[test]
+    fn food_manager_spawns_the_same_food_for_the_same_seed() {
+        let board = Board::new(50, 50, 1, 1);
+        let mut first = FoodManager::new();
+        let mut second = FoodManager::new();
+        let mut first_rng = fastrand::Rng::with_seed(42);
+        let mut second_rng = fastrand::Rng::with_seed(42);
+        for _ in 0..5 {
+            first.spawn_food(&board, &[], &mut first_rng);
+            second.spawn_food(&board, &[], &mut second_rng);
+        }
+        assert!(first.food_positions().eq(second.food_positions()));
+    }
+
+    #[test]
+    fn food_manager_stops_spawning_on_full_board() {
+        let board = Board::new(2, 1, 1, 1);
+        let mut snake = Snake::new(0, 0);
+        snake.grow(1);
+        snake.step(&board);
+
+        let mut manager = FoodManager::new();
+        assert!(!manager.spawn_food(&board, &[snake], &mut fastrand::Rng::new()));
+        assert_eq!(manager.foods.len(), 0);
+    }
+}
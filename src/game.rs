@@ -1,29 +1,81 @@
+use crate::achievements::{self, Achievement, AchievementInfo, Achievements, Progress};
+use crate::autopilot::HamiltonianCycle;
 use crate::board::Board;
+use crate::controller::{ControllerKind, SnakeController, World};
 use crate::food::FoodManager;
-use crate::level::LevelManager;
-use crate::snake::Snake;
-use crate::{GameEvent, GameState, Key};
+use crate::canvas::Canvas;
+use crate::highscores::{HighScore, HighScores, DEFAULT_TABLE_SIZE};
+use crate::hud::{self, HudInfo};
+use crate::keymap::{self, Action, KeyMap};
+use crate::level::{LevelManager, Result};
+use crate::palette::{Palette, PRESETS};
+use crate::particles::ParticleSystem;
+use crate::tileset::Tileset;
+use crate::viewport;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::export::{self, GifRecorder};
+use crate::replay::{mode_name, Replay};
+use crate::scoring::Scoring;
+use crate::snake::{DeathCause, MoveOutcome, Snake, INITIAL_SPEED};
+use crate::stats::{LifetimeStats, RunStats};
+use crate::terminal;
+use crate::verify::{Rejection, ScoreClaim};
+use crate::events::{Event, EventObserver, EventObservers, EventQueue, GameEventInfo};
+use crate::{Direction, GameEvent, GameObject, GameState, Key};
 use wasm_bindgen::prelude::wasm_bindgen;
-use wasm_bindgen::JsValue;
 
 const GRID_WIDTH: usize = 100;
 const GRID_HEIGHT: usize = 100;
+/// Below this many pixels per cell the camera shows part of the board instead of all of it.
+const MIN_CELL_SIZE: usize = 4;
 
 const INITIAL_SNAKE_LENGTH: usize = 5;
 const SNAKE_GROWTH_RATE: usize = 8;
 
 const SPEED_INC: f32 = 0.05;
 
+/// The simulation advances in fixed steps, so a game only depends on its seed and inputs and
+/// not on the frame rate it was played at.
+pub(crate) const TICK_MILLIS: f32 = 1000.0 / 120.0;
+
+const PLAYER: usize = 0;
+const OPPONENT_SPAWN_RUN: usize = 5;
+const OPPONENT_SPAWN_ATTEMPTS: usize = 1000;
+
 #[wasm_bindgen]
 pub struct GameWasm {
     score: u32,
     screen_buffer: Vec<u8>,
     game_state: GameState,
-    snake: Snake,
+    rng: fastrand::Rng,
+    tick_accumulator: f32,
+    ticks: u64,
+    snakes: Vec<Snake>,
+    controllers: Vec<Box<dyn SnakeController>>,
+    player_controller: ControllerKind,
+    opponents: Vec<ControllerKind>,
     board: Board,
     food_manager: FoodManager,
-    game_event_listener: Option<js_sys::Function>,
-    level_manager: LevelManager<'static>,
+    events: EventQueue,
+    observers: EventObservers,
+    level_manager: LevelManager,
+    level_name: String,
+    replay: Replay,
+    show_hud: bool,
+    particles: ParticleSystem,
+    screen_shake: bool,
+    show_minimap: bool,
+    death_cause: Option<DeathCause>,
+    scoring: Scoring,
+    high_scores: HighScores,
+    run_stats: RunStats,
+    lifetime_stats: LifetimeStats,
+    run_counted: bool,
+    achievements: Achievements,
+    key_map: KeyMap,
+    /// Whether games count for the lifetime stats and achievements, off for agents training in
+    /// a `SnakeEnv`.
+    keeps_records: bool,
 }
 
 #[wasm_bindgen]
@@ -31,43 +83,114 @@ impl GameWasm {
     #[wasm_bindgen(constructor)]
     pub fn new(width: usize, height: usize) -> GameWasm {
         let size = width * height * 4;
-        let cell_width = width / GRID_WIDTH;
-        let cell_height = height / GRID_HEIGHT;
 
-        let mut level_manager = LevelManager::new(GRID_WIDTH * GRID_HEIGHT);
+        let mut level_manager = LevelManager::new(GRID_WIDTH, GRID_HEIGHT);
         level_manager.add_level("Board 1", include_bytes!("../assets/levels/level01.txt")).unwrap();
         level_manager.add_level("Board 2", include_bytes!("../assets/levels/level02.txt")).unwrap();
         level_manager.add_level("Board 3", include_bytes!("../assets/levels/level03.txt")).unwrap();
 
-        let mut board = Board::new(GRID_WIDTH, GRID_HEIGHT, cell_width, cell_height);
-        let starting_level = level_manager.get_level("Board 1").unwrap();
-        board.set_level_data(starting_level);
-
-        let mut snake = Snake::new(GRID_WIDTH / 2, GRID_HEIGHT / 2);
-        snake.grow(INITIAL_SNAKE_LENGTH - 1);
+        let starting_level = "Board 1";
+        let mut board = Board::new(GRID_WIDTH, GRID_HEIGHT, 1, 1);
+        board.set_min_cell_size(MIN_CELL_SIZE);
+        board.set_screen_size(width, height);
+        board.set_level_data(level_manager.get_level(starting_level).unwrap());
 
-        let mut food_manager = FoodManager::new();
-        food_manager.spawn_food(&board, &snake);
-
-        GameWasm {
+        let mut game = GameWasm {
             score: 0,
             screen_buffer: vec![0; size],
             game_state: GameState::Paused,
-            snake,
+            rng: fastrand::Rng::new(),
+            tick_accumulator: 0.0,
+            ticks: 0,
+            snakes: Vec::new(),
+            controllers: Vec::new(),
+            player_controller: ControllerKind::Keyboard,
+            opponents: Vec::new(),
             board,
-            food_manager,
+            food_manager: FoodManager::new(),
             level_manager,
-            game_event_listener: None,
-        }
+            events: EventQueue::new(),
+            observers: EventObservers::new(),
+            level_name: starting_level.to_string(),
+            replay: Replay::new(starting_level, 0, Vec::new()),
+            show_hud: true,
+            particles: ParticleSystem::new(),
+            screen_shake: true,
+            show_minimap: true,
+            death_cause: None,
+            scoring: Scoring::new(),
+            high_scores: HighScores::new(DEFAULT_TABLE_SIZE),
+            run_stats: RunStats::new(0, INITIAL_SPEED),
+            lifetime_stats: LifetimeStats::new(),
+            run_counted: false,
+            achievements: Achievements::new(achievements::built_in()),
+            key_map: KeyMap::default(),
+            keeps_records: true,
+        };
+        game.reset();
+        game.game_state = GameState::Paused;
+        game
     }
 
+    /// Starts a new game with a fresh seed.
     fn reset(&mut self) {
-        self.score = 0;
-        self.game_state = GameState::Running;
-        self.snake = Snake::new(GRID_WIDTH / 2, GRID_HEIGHT / 2);
-        self.snake.grow(SNAKE_GROWTH_RATE);
-        self.food_manager = FoodManager::new();
-        self.food_manager.spawn_food(&self.board, &self.snake);
+        let seed = self.rng.u64(..);
+        self.reset_with_seed(seed);
+    }
+
+    /// The autopilot can only collect food on its Hamiltonian cycle, so food is kept off the
+    /// few cells the cycle doesn't cover while it is driving.
+    fn food_spawn_area(&self) -> Option<Vec<bool>> {
+        (self.player_controller == ControllerKind::Autopilot)
+            .then(|| HamiltonianCycle::new(&self.board).covered_cells())
+    }
+
+    /// Drops a computer snake onto a random spot with some free cells ahead of it.
+    fn spawn_opponent(&mut self, kind: ControllerKind) {
+        for _ in 0..OPPONENT_SPAWN_ATTEMPTS {
+            let x = self.rng.usize(0..self.board.get_width());
+            let y = self.rng.usize(0..self.board.get_height());
+            let mut run = (0..OPPONENT_SPAWN_RUN).scan((x, y), |pos, _| {
+                let current = *pos;
+                *pos = self.board.neighbour(pos.0, pos.1, Direction::Right);
+                Some(current)
+            });
+            let is_free = run.all(|(x, y)| {
+                !self.board.is_wall_at(x, y)
+                    && !self.food_manager.is_food_at(x, y)
+                    && !self.snakes.iter().any(|snake| snake.is_snake_at(x, y))
+            });
+            if is_free {
+                let mut snake = Snake::new(x, y).with_object(GameObject::Opponent);
+                snake.grow(INITIAL_SNAKE_LENGTH - 1);
+                self.snakes.push(snake);
+                self.controllers.push(kind.create());
+                return;
+            }
+        }
+    }
+
+    /// Lets a computer controlled snake join the current level. It stays in the game after a
+    /// reset or level change until `remove_computer_snakes` is called.
+    #[wasm_bindgen]
+    pub fn add_computer_snake(&mut self, kind: ControllerKind) {
+        self.opponents.push(kind);
+        self.spawn_opponent(kind);
+    }
+
+    #[wasm_bindgen]
+    pub fn remove_computer_snakes(&mut self) {
+        self.opponents.clear();
+        self.snakes.truncate(1);
+        self.controllers.truncate(1);
+    }
+
+    /// Hands the player's snake to another controller, e.g. a bot for automated playtesting.
+    #[wasm_bindgen]
+    pub fn set_player_controller(&mut self, kind: ControllerKind) {
+        self.player_controller = kind;
+        self.controllers[PLAYER] = kind.create();
+        self.food_manager.set_spawn_area(self.food_spawn_area());
     }
 
     #[wasm_bindgen]
@@ -77,13 +200,35 @@ impl GameWasm {
 
     #[wasm_bindgen]
     pub fn load_level(&mut self, level_name: &str) {
-        if let Some(level_data) = self.level_manager.get_level(level_name) {
-            self.board.set_level_data(level_data);
+        if self.set_level(level_name) {
             self.reset();
             self.game_state = GameState::Paused;
-            self.trigger_event(GameEvent::GamePause);
+            self.trigger_event(Event::LevelLoaded {
+                name: level_name.to_string(),
+                width: self.board.get_width(),
+                height: self.board.get_height(),
+            });
+            self.trigger_event(Event::GamePause);
         }
     }
+    /// Changes the size of the screen buffer, e.g. to follow the canvas when the window is
+    /// resized. The buffer moves, so `get_screen_buffer` has to be called again afterwards.
+    #[wasm_bindgen]
+    pub fn resize(&mut self, width: usize, height: usize) {
+        self.screen_buffer = vec![0; width * height * 4];
+        self.board.set_screen_size(width, height);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_screen_width(&self) -> usize {
+        self.board.get_screen_width()
+    }
+
+    #[wasm_bindgen]
+    pub fn get_screen_height(&self) -> usize {
+        self.board.get_screen_height()
+    }
+
     #[wasm_bindgen]
     pub fn get_screen_buffer(&self) -> *const u8 {
         self.screen_buffer.as_ptr()
@@ -98,76 +243,492 @@ impl GameWasm {
         self.score
     }
 
+    /// What the next piece of food is multiplied by if the player eats it before the combo runs
+    /// out.
+    #[wasm_bindgen]
+    pub fn get_combo_multiplier(&self) -> u32 {
+        self.scoring.get_multiplier()
+    }
+
+    /// Milliseconds of game time until the combo runs out.
+    #[wasm_bindgen]
+    pub fn get_combo_millis_left(&self) -> f32 {
+        self.scoring.get_combo_millis_left()
+    }
+
     #[wasm_bindgen]
     pub fn update(&mut self, delta_time: f32) {
+        self.particles.update(delta_time);
+        if self.game_state == GameState::Paused && self.ticks > 0 {
+            self.run_stats.paused_millis += delta_time as f64;
+        }
         if self.game_state != GameState::Running {
             return;
         }
 
-        if !self.snake.move_forward(&self.board, delta_time) {
-            self.trigger_event(GameEvent::GameOver);
-            self.game_state = GameState::GameOver;
-            return;
+        self.tick_accumulator += delta_time;
+        while self.tick_accumulator >= TICK_MILLIS && self.game_state == GameState::Running {
+            self.tick_accumulator -= TICK_MILLIS;
+            self.tick();
+        }
+    }
+
+    /// Advances the simulation by one fixed time step.
+    pub(crate) fn tick(&mut self) {
+        self.ticks += 1;
+        self.replay.ticks = self.ticks;
+        self.scoring.tick();
+        self.run_stats.time_alive_millis += TICK_MILLIS as f64;
+        let mut index = 0;
+        while index < self.snakes.len() {
+            let distance = self.snakes[index].advance(TICK_MILLIS);
+            let outcome = (0..distance)
+                .map(|_| self.step_snake(index))
+                .find(|&outcome| outcome != MoveOutcome::Moved)
+                .unwrap_or(MoveOutcome::Moved);
+            let MoveOutcome::Died(cause) = outcome else {
+                index += 1;
+                continue;
+            };
+            let (x, y) = self.snakes[index].get_head_pos();
+            if index == PLAYER {
+                self.death_cause = Some(cause);
+                self.count_run();
+                self.trigger_event(Event::GameOver { x, y, score: self.score, cause });
+                self.game_state = GameState::GameOver;
+                return;
+            } else {
+                let color = self.board.get_palette().opponent;
+                self.particles.scatter(self.snakes[index].body(), color);
+                self.trigger_event(Event::OpponentDied { x, y, cause });
+                self.snakes.remove(index);
+                self.controllers.remove(index);
+            }
+        }
+        self.check_achievements();
+    }
+
+    /// Lets the snake's controller steer and moves the snake one cell, unless it crashes into a
+    /// wall, itself or another snake.
+    fn step_snake(&mut self, index: usize) -> MoveOutcome {
+        let world = World {
+            board: &self.board,
+            snakes: &self.snakes,
+            foods: &self.food_manager,
+            me: index,
+        };
+        let previous_direction = self.snakes[index].get_direction();
+        if let Some(direction) = self.controllers[index].next_direction(&world) {
+            self.snakes[index].set_direction(direction);
+        }
+
+        let outcome = self.snakes[index].step(&self.board);
+        if outcome != MoveOutcome::Moved {
+            return outcome;
+        }
+        if index == PLAYER {
+            self.update_run_stats(previous_direction);
+        }
+
+        let (head_x, head_y) = self.snakes[index].get_head_pos();
+        let hit_other_snake = self
+            .snakes
+            .iter()
+            .enumerate()
+            .any(|(other, snake)| other != index && snake.is_snake_at(head_x, head_y));
+        if hit_other_snake {
+            return MoveOutcome::Died(DeathCause::HitOtherSnake { x: head_x, y: head_y });
         }
 
-        let (head_x, head_y) = self.snake.get_head_pos();
         if self.food_manager.is_food_at(head_x, head_y) {
-            self.snake_eats_food(head_x, head_y);
+            self.snake_eats_food(index, head_x, head_y);
+        }
+        MoveOutcome::Moved
+    }
+
+    /// Counts the cell the player just moved, and whether it turned away from something right
+    /// in front of it.
+    fn update_run_stats(&mut self, previous_direction: Direction) {
+        let player = &self.snakes[PLAYER];
+        self.run_stats.distance += 1;
+        self.run_stats.update_records(player.len(), player.get_speed());
+        if player.get_direction() == previous_direction {
+            return;
+        }
+        self.run_stats.turns += 1;
+        if player.get_direction() == previous_direction.turn_left() {
+            self.run_stats.left_turns += 1;
+        }
+        let (head_x, head_y) = player.body().nth(1).copied().unwrap_or(player.get_head_pos());
+        let (ahead_x, ahead_y) = self.board.neighbour(head_x, head_y, previous_direction);
+        if self.board.is_wall_at(ahead_x, ahead_y) || self.snakes.iter().any(|snake| snake.is_snake_at(ahead_x, ahead_y)) {
+            self.run_stats.near_misses += 1;
         }
     }
 
-    fn snake_eats_food(&mut self, x: usize, y: usize) {
-        self.trigger_event(GameEvent::EatFood);
-        self.snake.grow(2);
-        self.snake.increase_speed(SPEED_INC);
+    /// Adds the current game to the lifetime stats once it is over or abandoned. Like
+    /// achievements, only games the player steers count.
+    fn count_run(&mut self) {
+        if self.ticks > 0 && !self.run_counted && self.is_players_game() {
+            self.lifetime_stats.add_run(&self.run_stats);
+            self.run_counted = true;
+        }
+    }
+
+    fn snake_eats_food(&mut self, index: usize, x: usize, y: usize) {
+        if index == PLAYER {
+            let player = &self.snakes[PLAYER];
+            let points = self.scoring.eat_food(player.get_speed(), player.len());
+            self.update_score(points);
+            self.run_stats.food_eaten += 1;
+            self.trigger_event(Event::EatFood { x, y, points });
+        }
+        self.snakes[index].grow(2);
+        self.snakes[index].increase_speed(SPEED_INC);
+        if index == PLAYER {
+            let speed = self.snakes[PLAYER].get_speed();
+            self.run_stats.update_records(self.snakes[PLAYER].len(), speed);
+            self.trigger_event(Event::SpeedChanged { speed });
+        }
         self.food_manager.take_food(x, y);
-        self.update_score(10);
-        self.food_manager.spawn_food(&self.board, &self.snake);
+        if !self.food_manager.spawn_food(&self.board, &self.snakes, &mut self.rng) {
+            // The board is full, the snake can only keep following its tail
+            self.snakes[index].stop_growing();
+        } else if let Some((food_x, food_y)) = self.food_manager.food_positions().last() {
+            self.particles.sparkle(food_x, food_y);
+        }
     }
 
     #[wasm_bindgen]
     pub fn render(&mut self) {
-        self.board.draw_level();
-        self.food_manager.render_foods_to_board(&mut self.board);
-        self.snake.render_to_board(&mut self.board);
+        let (head_x, head_y) = self.snakes[PLAYER].get_head_pos();
+        self.board.follow(head_x, head_y);
+        self.draw_board();
         self.board
             .render_to_buffer(self.screen_buffer.as_mut_slice());
+        self.render_effects();
+        if self.show_minimap && self.board.get_viewport().is_scrolling() {
+            let mut canvas = Canvas::new(&mut self.screen_buffer, self.board.get_screen_width(), self.board.get_screen_height());
+            let rect = viewport::render_minimap(&mut canvas, &self.board);
+            self.board.add_overlay(rect);
+        }
+        if self.show_hud {
+            self.render_hud();
+        }
     }
 
-    fn trigger_event(&self, event: GameEvent) {
-        if let Some(callback) = &self.game_event_listener {
-            let this = JsValue::NULL;
-            let event = JsValue::from(event);
-            let _ = callback.call1(&this, &event);
-        }
+    /// Shows the whole board in a corner while the camera only shows part of it. On by default.
+    #[wasm_bindgen]
+    pub fn set_show_minimap(&mut self, show: bool) {
+        self.show_minimap = show;
+        self.board.invalidate();
     }
 
+    /// The smallest size of a cell in pixels. Boards that would need smaller cells to fit onto
+    /// the screen are shown through a camera following the player. 0 always shows the whole
+    /// board.
     #[wasm_bindgen]
-    pub fn key_down(&mut self, key: &str) {
-        let key = key.into();
-        if (key == Key::Space) && self.game_state == GameState::Running {
-            self.trigger_event(GameEvent::GamePause);
-            self.game_state = GameState::Paused;
-            return;
-        } else if (key == Key::Space) && self.game_state == GameState::Paused {
-            self.trigger_event(GameEvent::GameStart);
-            self.game_state = GameState::Running;
-            return;
-        } else if (key == Key::Space) && self.game_state == GameState::GameOver {
-            self.trigger_event(GameEvent::GameStart);
-            self.reset();
+    pub fn set_min_cell_size(&mut self, size: usize) {
+        self.board.set_min_cell_size(size);
+    }
+
+    /// Shakes the screen when the player's snake dies. On by default.
+    #[wasm_bindgen]
+    pub fn set_screen_shake(&mut self, enabled: bool) {
+        self.screen_shake = enabled;
+    }
+
+    /// Draws score, length, speed, level name and state banners into the screen buffer. On by
+    /// default, hosts that draw their own HUD can turn it off.
+    #[wasm_bindgen]
+    pub fn set_show_hud(&mut self, show: bool) {
+        self.show_hud = show;
+        self.board.invalidate();
+    }
+
+    /// The screen areas the last `render` changed, as flat `x, y, width, height` quadruples in
+    /// pixels, so the host only has to upload those parts of the screen buffer.
+    #[wasm_bindgen]
+    pub fn get_dirty_rects(&self) -> Vec<u32> {
+        self.board.get_dirty_rects().to_vec()
+    }
+
+    /// Makes the next `render` repaint the whole screen buffer.
+    #[wasm_bindgen]
+    pub fn invalidate_screen(&mut self) {
+        self.board.invalidate();
+    }
+
+    /// Draws the board with sprites from an RGBA atlas `atlas_width` pixels wide, made of square
+    /// tiles `tile_size` pixels wide. See `tileset.rs` for the order of the tiles.
+    #[wasm_bindgen]
+    pub fn load_tileset(&mut self, atlas: Vec<u8>, atlas_width: usize, tile_size: usize) -> std::result::Result<(), String> {
+        let tileset = Tileset::new(atlas, atlas_width, tile_size)?;
+        self.board.set_tileset(Some(tileset));
+        Ok(())
+    }
+
+    /// Goes back to drawing the board with flat colours.
+    #[wasm_bindgen]
+    pub fn clear_tileset(&mut self) {
+        self.board.set_tileset(None);
+    }
+
+    /// Draws snakes moving smoothly between cells instead of jumping a whole cell at a time. The
+    /// game itself still moves them cell by cell.
+    #[wasm_bindgen]
+    pub fn set_smooth_movement(&mut self, enabled: bool) {
+        self.board.set_smooth_movement(enabled);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_palette_names(&self) -> Vec<String> {
+        PRESETS.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Switches to one of the built-in palettes from `get_palette_names`.
+    #[wasm_bindgen]
+    pub fn set_palette(&mut self, name: &str) -> std::result::Result<(), String> {
+        let palette = Palette::preset(name).ok_or_else(|| format!("Unknown palette: {name}"))?;
+        self.board.set_palette(palette);
+        Ok(())
+    }
+
+    /// Switches to a palette written as `name #rrggbb` lines, see `palette.rs` for the format.
+    #[wasm_bindgen]
+    pub fn load_palette(&mut self, text: &str) -> std::result::Result<(), String> {
+        let palette = text.parse()?;
+        self.board.set_palette(palette);
+        Ok(())
+    }
+
+    /// The current palette in the format `load_palette` reads.
+    #[wasm_bindgen]
+    pub fn get_palette(&self) -> String {
+        self.board.get_palette().to_string()
+    }
+
+    /// Puts the level, the food and the snakes into the board cells.
+    pub(crate) fn draw_board(&mut self) {
+        self.board.draw_level();
+        self.food_manager.render_foods_to_board(&mut self.board);
+        for snake in &self.snakes {
+            snake.render_to_board(&mut self.board);
+        }
+        if self.board.get_smooth_movement() {
+            for snake in &self.snakes {
+                snake.render_motion_to_board(&mut self.board);
+            }
+        }
+    }
+
+    /// Draws the particles over the board and moves the picture while the screen shakes.
+    fn render_effects(&mut self) {
+        if self.particles.is_empty() {
             return;
         }
+        let (width, height) = (self.board.get_screen_width(), self.board.get_screen_height());
+        let mut canvas = Canvas::new(&mut self.screen_buffer, width, height);
+        for rect in self.particles.render(&mut canvas, &self.board) {
+            self.board.add_overlay(rect);
+        }
 
-        if self.game_state != GameState::Running {
+        let (dx, dy) = self.particles.shake_offset();
+        let (cell_width, cell_height) = self.board.cell_size();
+        let (dx, dy) = ((dx * cell_width as f32) as isize, (dy * cell_height as f32) as isize);
+        if dx != 0 || dy != 0 {
+            canvas.shift(dx, dy, self.board.get_palette().empty);
+            self.board.add_overlay((0, 0, width, height));
+        }
+    }
+
+    fn render_hud(&mut self) {
+        let player = &self.snakes[PLAYER];
+        let info = HudInfo {
+            level_name: &self.level_name,
+            score: self.score,
+            length: player.len(),
+            speed: player.get_speed(),
+            state: self.game_state,
+            started: self.ticks > 0,
+            death_cause: self.death_cause,
+            multiplier: self.scoring.get_multiplier(),
+            combo_left: self.scoring.get_combo_left(),
+        };
+        let mut canvas = Canvas::new(&mut self.screen_buffer, self.board.get_screen_width(), self.board.get_screen_height());
+        for rect in hud::render_hud(&mut canvas, &info) {
+            self.board.add_overlay(rect);
+        }
+    }
+
+    /// Shows the effects of an event, tells the observers about it and queues it for the host, see
+    /// `poll_events`.
+    fn trigger_event(&mut self, event: Event) {
+        let palette = *self.board.get_palette();
+        match event {
+            Event::EatFood { x, y, .. } => self.particles.burst(x, y, palette.food),
+            Event::GameOver { .. } => {
+                self.particles.scatter(self.snakes[PLAYER].body(), palette.snake);
+                if self.screen_shake {
+                    self.particles.shake();
+                }
+            }
+            _ => {}
+        }
+        self.observers.notify(&event);
+        let unlocked = matches!(event, Event::AchievementUnlocked { .. });
+        if !unlocked {
+            self.achievements.record(&event, self.run_stats.time_alive_millis);
+        }
+        self.events.push(event);
+        if !unlocked {
+            self.check_achievements();
+        }
+    }
+
+    fn is_players_game(&self) -> bool {
+        self.keeps_records && self.player_controller == ControllerKind::Keyboard
+    }
+
+    /// Unlocks the achievements the game now meets. Only games the player steers count.
+    fn check_achievements(&mut self) {
+        if !self.is_players_game() {
             return;
         }
-        self.snake.change_direction(key);
+        let progress = Progress {
+            level: &self.level_name,
+            score: self.score,
+            length: self.snakes[PLAYER].len(),
+            run: &self.run_stats,
+            lifetime: &self.lifetime_stats,
+        };
+        for achievement in self.achievements.check(&progress) {
+            self.trigger_event(Event::AchievementUnlocked { id: achievement.id, name: achievement.name });
+        }
+    }
+
+    /// Every achievement with whether it's unlocked.
+    #[wasm_bindgen]
+    pub fn get_achievements(&self) -> Vec<AchievementInfo> {
+        self.achievements.get_infos()
+    }
+
+    /// The ids of the unlocked achievements as text for the host to store.
+    #[wasm_bindgen]
+    pub fn get_achievements_text(&self) -> String {
+        self.achievements.unlocked_text()
+    }
+
+    /// Replaces the unlocked achievements with ones written by `get_achievements_text`.
+    #[wasm_bindgen]
+    pub fn load_achievements(&mut self, text: &str) -> std::result::Result<(), String> {
+        self.achievements.load(text)
+    }
+
+    /// Takes the events that happened since the last call, oldest first. Hosts call this once
+    /// per frame, e.g. to play sounds. Only the latest events are kept if nobody polls.
+    #[wasm_bindgen]
+    pub fn poll_events(&mut self) -> Vec<GameEventInfo> {
+        self.take_events().into_iter().map(GameEventInfo::from).collect()
+    }
+
+    /// Handles a key by its `KeyboardEvent.code`, see `set_key_scheme` and `bind_key` for what
+    /// keys do. Keys that aren't bound are ignored.
+    #[wasm_bindgen]
+    pub fn key_down(&mut self, code: &str) {
+        if let Some(action) = self.key_map.action(code) {
+            self.press(action);
+        }
+    }
+
+    /// Does what a key bound to `action` does, for input that doesn't come from keys, e.g.
+    /// touches on either half of the screen or gamepad buttons.
+    #[wasm_bindgen]
+    pub fn press(&mut self, action: Action) {
+        match (action, self.game_state) {
+            (Action::Pause | Action::Menu, GameState::Running) => {
+                self.trigger_event(Event::GamePause);
+                self.game_state = GameState::Paused;
+            }
+            (Action::Pause, GameState::Paused) => {
+                self.trigger_event(Event::GameStart);
+                self.game_state = GameState::Running;
+            }
+            (Action::Pause | Action::Restart, _) => {
+                self.trigger_event(Event::GameStart);
+                self.reset();
+            }
+            (Action::Menu, _) => {}
+            (steering, GameState::Running) => {
+                if let Some(key) = steering.steering_key() {
+                    self.player_key_down(key);
+                }
+            }
+            _ => {}
+        }
+        if action == Action::Menu {
+            self.trigger_event(Event::MenuRequested);
+        }
+    }
+
+    #[wasm_bindgen]
+    pub fn get_key_scheme_names(&self) -> Vec<String> {
+        keymap::SCHEMES.iter().map(|name| name.to_string()).collect()
+    }
+
+    /// Replaces all key bindings with one of the schemes from `get_key_scheme_names`.
+    #[wasm_bindgen]
+    pub fn set_key_scheme(&mut self, name: &str) -> std::result::Result<(), String> {
+        self.key_map = KeyMap::scheme(name).ok_or_else(|| format!("Unknown key scheme: {name}"))?;
+        Ok(())
+    }
+
+    /// Makes the key with this `KeyboardEvent.code` do `action`.
+    #[wasm_bindgen]
+    pub fn bind_key(&mut self, code: &str, action: Action) {
+        self.key_map.bind(code, action);
+    }
+
+    /// Makes a key do nothing. Returns `false` if it wasn't bound.
+    #[wasm_bindgen]
+    pub fn unbind_key(&mut self, code: &str) -> bool {
+        self.key_map.unbind(code)
+    }
+
+    /// The codes of the keys bound to an action.
+    #[wasm_bindgen]
+    pub fn get_keys_for(&self, action: Action) -> Vec<String> {
+        self.key_map.keys_for(action)
+    }
+
+    /// The key bindings as text for the host to store, e.g. in `localStorage`.
+    #[wasm_bindgen]
+    pub fn get_key_bindings(&self) -> String {
+        self.key_map.to_string()
+    }
+
+    /// Replaces the key bindings with ones written by `get_key_bindings`.
+    #[wasm_bindgen]
+    pub fn load_key_bindings(&mut self, text: &str) -> std::result::Result<(), String> {
+        self.key_map = text.parse()?;
+        Ok(())
+    }
+
+    fn player_key_down(&mut self, key: Key) {
+        if key.steers() {
+            self.replay.record(self.ticks, key);
+        }
+        self.controllers[PLAYER].key_down(key);
+    }
+
+    #[wasm_bindgen]
+    pub fn get_level_name(&self) -> String {
+        self.level_name.clone()
     }
 
     #[wasm_bindgen]
-    pub fn add_game_event_listener(&mut self, callback: js_sys::Function) {
-        self.game_event_listener = Some(callback);
+    pub fn get_snake_length(&self) -> usize {
+        self.snakes[PLAYER].len()
     }
 
     #[wasm_bindgen]
@@ -184,4 +745,788 @@ impl GameWasm {
     pub fn get_game_state(&self) -> GameState {
         self.game_state
     }
+
+    /// Why the player's snake died, e.g. "wall" or "own-body", or nothing while it's alive.
+    #[wasm_bindgen]
+    pub fn get_death_cause_name(&self) -> Option<String> {
+        self.death_cause.map(|cause| cause.name().to_string())
+    }
+
+    /// What the player did in the current game so far.
+    #[wasm_bindgen]
+    pub fn get_run_stats(&self) -> RunStats {
+        self.run_stats
+    }
+
+    /// Totals of all finished games, including games abandoned by a restart or level change.
+    #[wasm_bindgen]
+    pub fn get_lifetime_stats(&self) -> LifetimeStats {
+        self.lifetime_stats
+    }
+
+    /// The lifetime stats as text for the host to store, e.g. in `localStorage`.
+    #[wasm_bindgen]
+    pub fn get_lifetime_stats_text(&self) -> String {
+        self.lifetime_stats.to_string()
+    }
+
+    /// Replaces the lifetime stats with ones written by `get_lifetime_stats_text`.
+    #[wasm_bindgen]
+    pub fn load_lifetime_stats(&mut self, text: &str) -> std::result::Result<(), String> {
+        self.lifetime_stats = text.parse()?;
+        Ok(())
+    }
+
+    /// The high-score table the current game counts for: "classic", or the player's bot and the
+    /// computer snakes, e.g. "autopilot" or "vs-greedy-survival".
+    #[wasm_bindgen]
+    pub fn get_mode(&self) -> String {
+        mode_name(self.player_controller, &self.opponents)
+    }
+
+    /// Whether the current score would make it into the high scores of this level and mode.
+    #[wasm_bindgen]
+    pub fn is_high_score(&self) -> bool {
+        self.high_scores.qualifies(&self.level_name, &self.get_mode(), self.score)
+    }
+
+    /// Puts the current game into the high scores, usually once it's over. `date` is the
+    /// host's `Date.now()`. Returns the rank counted from 0, or nothing if the score wasn't
+    /// good enough. The table is kept across resets and level changes. The replay isn't kept if
+    /// the controllers changed since the game started, as it wouldn't play the same game.
+    #[wasm_bindgen]
+    pub fn submit_high_score(&mut self, name: &str, date: f64, keep_replay: bool) -> Option<usize> {
+        let mode = self.get_mode();
+        let entry = HighScore {
+            name: name.to_string(),
+            score: self.score,
+            length: self.snakes[PLAYER].len(),
+            duration_millis: (self.ticks as f32 * TICK_MILLIS).round() as u32,
+            date,
+            seed: self.replay.seed,
+            replay: (keep_replay && self.replay.mode() == mode).then(|| self.replay.clone()),
+        };
+        self.high_scores.add(&self.level_name, &mode, entry)
+    }
+
+    /// The high scores of a level and mode, best first.
+    #[wasm_bindgen]
+    pub fn get_high_scores(&self, level_name: &str, mode: &str) -> Vec<HighScore> {
+        self.high_scores.get(level_name, mode).to_vec()
+    }
+
+    /// All high scores as text for the host to store, e.g. in `localStorage`.
+    #[wasm_bindgen]
+    pub fn get_high_scores_text(&self) -> String {
+        self.high_scores.to_string()
+    }
+
+    /// Replaces the high scores with ones written by `get_high_scores_text`.
+    #[wasm_bindgen]
+    pub fn load_high_scores(&mut self, text: &str) -> std::result::Result<(), String> {
+        self.high_scores.load(text)
+    }
+
+    #[wasm_bindgen]
+    pub fn clear_high_scores(&mut self) {
+        self.high_scores.clear();
+    }
+}
+
+/// Native entry points for running the game without a browser.
+impl GameWasm {
+    /// Restarts the current level with a seeded random generator, so the same seed and inputs
+    /// always play out the same game.
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.count_run();
+        self.rng = fastrand::Rng::with_seed(seed);
+        self.replay =
+            Replay::new(&self.level_name, seed, self.opponents.clone()).with_player(self.player_controller);
+        self.score = 0;
+        self.game_state = GameState::Running;
+        self.tick_accumulator = 0.0;
+        self.ticks = 0;
+        self.particles.clear();
+        self.death_cause = None;
+        self.scoring = Scoring::new();
+        let mut snake = Snake::new(self.board.get_width() / 2, self.board.get_height() / 2);
+        snake.grow(SNAKE_GROWTH_RATE);
+        self.run_stats = RunStats::new(snake.len(), snake.get_speed());
+        self.run_counted = false;
+        self.achievements.start_run();
+        self.snakes = vec![snake];
+        self.controllers = vec![self.player_controller.create()];
+        for kind in self.opponents.clone() {
+            self.spawn_opponent(kind);
+        }
+        self.food_manager = FoodManager::new();
+        self.food_manager.set_spawn_area(self.food_spawn_area());
+        self.food_manager.spawn_food(&self.board, &self.snakes, &mut self.rng);
+    }
+
+    /// Adds a level, e.g. one loaded from disk. Levels written as lines of equal length can have
+    /// any size, others must have the size of the built-in ones.
+    pub fn add_level(&mut self, level_name: &str, level_data: &[u8]) -> Result<()> {
+        self.level_manager.add_level(level_name, level_data)
+    }
+
+    /// Keeps simulating until the game is over or `max_ticks` ticks have passed in total.
+    pub fn run_until_game_over(&mut self, max_ticks: u64) {
+        while self.game_state == GameState::Running && self.ticks < max_ticks {
+            self.tick();
+        }
+    }
+
+    /// Plays a recorded game from the start with the recorded controllers, pressing its keys at
+    /// the recorded ticks.
+    pub fn play_replay(&mut self, replay: &Replay) -> Result<()> {
+        self.play_replay_with(replay, |_| {})
+    }
+
+    /// Plays a replay like `play_replay` and calls `after_tick` after every tick. Particles move
+    /// along with the ticks, as if the game was played in real time.
+    fn play_replay_with(&mut self, replay: &Replay, mut after_tick: impl FnMut(&mut Self)) -> Result<()> {
+        if !self.set_level(&replay.level) {
+            return Err(format!("Unknown level: {}", replay.level).into());
+        }
+        self.opponents = replay.opponents.clone();
+        self.set_player_controller(replay.player);
+        self.reset_with_seed(replay.seed);
+
+        let mut inputs = replay.inputs.iter().peekable();
+        while self.game_state == GameState::Running && self.ticks < replay.ticks {
+            let ticks = self.ticks;
+            while let Some(&(_, key)) = inputs.next_if(|(tick, _)| *tick <= ticks) {
+                self.player_key_down(key);
+            }
+            self.tick();
+            self.particles.tick();
+            after_tick(self);
+        }
+        Ok(())
+    }
+
+    /// Plays the replay of a claimed score and checks that it ends with that score and length,
+    /// with the snake alive or dying on the replay's last tick. Impossible input logs are
+    /// rejected without playing them. Like `play_replay` this leaves the game at the end of the
+    /// replay.
+    pub fn verify_claim(&mut self, claim: &ScoreClaim) -> std::result::Result<(), Rejection> {
+        claim.check_log()?;
+        let replay = &claim.replay;
+        self.play_replay(replay)
+            .map_err(|_| Rejection::UnknownLevel(replay.level.clone()))?;
+        if self.ticks < replay.ticks {
+            return Err(Rejection::EndedEarly { tick: self.ticks });
+        }
+        if self.score != claim.score {
+            return Err(Rejection::ScoreMismatch { claimed: claim.score, replayed: self.score });
+        }
+        let length = self.snakes[PLAYER].len();
+        if length != claim.length {
+            return Err(Rejection::LengthMismatch { claimed: claim.length, replayed: length });
+        }
+        Ok(())
+    }
+
+    /// Puts a level onto the board, resizing the board to the level. Returns `false` for unknown
+    /// levels.
+    fn set_level(&mut self, level_name: &str) -> bool {
+        let (Some(level_data), Some((width, height))) =
+            (self.level_manager.get_level(level_name), self.level_manager.get_level_size(level_name))
+        else {
+            return false;
+        };
+        if (width, height) != (self.board.get_width(), self.board.get_height()) {
+            self.board.resize(width, height);
+        }
+        self.board.set_level_data(level_data);
+        self.level_name = level_name.to_string();
+        true
+    }
+
+    /// Draws the game as ANSI coloured text for terminals.
+    pub fn render_ansi(&mut self) -> String {
+        self.draw_board();
+        terminal::render_ansi(&self.board)
+    }
+
+    /// Takes the events that happened since the last call, like `poll_events` but with the
+    /// payloads as Rust values.
+    pub fn take_events(&mut self) -> Vec<Event> {
+        self.events.drain()
+    }
+
+    /// Lets native code hear about events the moment they happen, e.g. to collect statistics.
+    /// An empty `kinds` subscribes to all events. Returns the handle for `unsubscribe`.
+    pub fn subscribe(&mut self, kinds: &[GameEvent], observer: impl EventObserver + 'static) -> u32 {
+        self.observers.subscribe(kinds, Box::new(observer))
+    }
+
+    /// Stops an observer from getting events. Returns `false` for unknown handles.
+    pub fn unsubscribe(&mut self, handle: u32) -> bool {
+        self.observers.unsubscribe(handle)
+    }
+
+    /// The recording of the current game.
+    pub fn get_replay(&self) -> &Replay {
+        &self.replay
+    }
+
+    /// Turns the player's snake right away instead of going through its controller.
+    pub fn steer_player(&mut self, direction: Direction) {
+        self.snakes[PLAYER].set_direction(direction);
+    }
+
+    /// Keeps games out of the lifetime stats and achievements when `false`, e.g. games an agent
+    /// plays instead of a person. On by default.
+    pub fn set_keeps_records(&mut self, enabled: bool) {
+        self.keeps_records = enabled;
+    }
+
+    /// Adds an achievement of the host's own to the built-in ones.
+    pub fn add_achievement(&mut self, achievement: Achievement) {
+        self.achievements.add(achievement);
+    }
+
+    /// Why the player's snake died, if it did.
+    pub fn get_death_cause(&self) -> Option<DeathCause> {
+        self.death_cause
+    }
+
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub(crate) fn get_board(&self) -> &Board {
+        &self.board
+    }
+
+    pub(crate) fn get_player(&self) -> &Snake {
+        &self.snakes[PLAYER]
+    }
+
+    pub(crate) fn get_food_manager(&self) -> &FoodManager {
+        &self.food_manager
+    }
+}
+
+/// Image export for headless builds.
+#[cfg(not(target_arch = "wasm32"))]
+impl GameWasm {
+    /// The screen buffer as PNG, as the last `render` left it.
+    pub fn screenshot_png(&self) -> Result<Vec<u8>> {
+        export::encode_png(&self.screen_buffer, self.board.get_screen_width(), self.board.get_screen_height())
+    }
+
+    /// A PNG of an empty level, scaled to fit into `size` x `size` pixels in the current palette.
+    pub fn level_thumbnail_png(&self, level_name: &str, size: usize) -> Result<Vec<u8>> {
+        let (Some(level_data), Some((width, height))) =
+            (self.level_manager.get_level(level_name), self.level_manager.get_level_size(level_name))
+        else {
+            return Err(format!("Unknown level: {level_name}").into());
+        };
+        let mut board = Board::new(width, height, 1, 1);
+        board.set_screen_size(size, size);
+        board.set_palette(*self.board.get_palette());
+        board.set_level_data(level_data);
+        board.draw_level();
+        let rgba = export::render_board(&mut board);
+        export::encode_png(&rgba, size, size)
+    }
+
+    /// Plays a replay and records it as an animated GIF of `width` x `height` pixels, with a
+    /// frame every `frame_step` ticks. The game is left at the end of the replay, at that size.
+    pub fn replay_gif(&mut self, replay: &Replay, width: usize, height: usize, frame_step: u64) -> Result<Vec<u8>> {
+        let frame_step = frame_step.max(1);
+        self.resize(width, height);
+        let mut recorder = GifRecorder::new(width, height, frame_step as f32 * TICK_MILLIS);
+        self.play_replay_with(replay, |game| {
+            if (game.ticks - 1).is_multiple_of(frame_step) {
+                game.render();
+                recorder.add_frame(&game.screen_buffer);
+            }
+        })?;
+        self.render();
+        recorder.add_frame(&self.screen_buffer);
+        recorder.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::scoring::FOOD_POINTS;
+    use crate::{Condition, Stat};
+
+    fn play_some_game(game: &mut GameWasm) {
+        let keys = ["ArrowUp", "ArrowLeft", "ArrowDown", "ArrowRight"];
+        for frame in 0..5000 {
+            if frame % 40 == 0 {
+                game.key_down(keys[(frame / 40) % keys.len()]);
+            }
+            game.update(TICK_MILLIS);
+        }
+    }
+
+    #[test]
+    fn game_is_deterministic_for_a_seed() {
+        let mut first = GameWasm::new(100, 100);
+        let mut second = GameWasm::new(100, 100);
+        first.add_computer_snake(ControllerKind::Greedy);
+        second.add_computer_snake(ControllerKind::Greedy);
+        first.reset_with_seed(3);
+        second.reset_with_seed(3);
+        play_some_game(&mut first);
+        play_some_game(&mut second);
+
+        assert_eq!(first.get_ticks(), second.get_ticks());
+        assert_eq!(first.get_score(), second.get_score());
+        assert_eq!(first.get_player().get_head_pos(), second.get_player().get_head_pos());
+    }
+
+    #[test]
+    fn game_plays_back_its_replay() {
+        let mut game = GameWasm::new(100, 100);
+        game.load_level("Board 2");
+        game.reset_with_seed(11);
+        play_some_game(&mut game);
+        let replay = game.get_replay().clone();
+        assert_eq!(replay.level, "Board 2");
+        assert_eq!(replay.ticks, game.get_ticks());
+
+        let mut playback = GameWasm::new(100, 100);
+        playback.play_replay(&replay).unwrap();
+        assert_eq!(playback.get_ticks(), game.get_ticks());
+        assert_eq!(playback.get_score(), game.get_score());
+        assert_eq!(playback.get_snake_length(), game.get_snake_length());
+        assert_eq!(playback.get_player().get_head_pos(), game.get_player().get_head_pos());
+        assert!(playback.get_game_state() == game.get_game_state());
+    }
+
+    #[test]
+    fn game_rejects_replays_of_unknown_levels() {
+        let mut game = GameWasm::new(100, 100);
+        let replay = Replay::new("Board 42", 1, Vec::new());
+        assert!(game.play_replay(&replay).is_err());
+    }
+
+    #[test]
+    fn game_renders_to_any_screen_size() {
+        let mut game = GameWasm::new(1000, 1000);
+        game.resize(1234, 777);
+        game.render();
+        assert_eq!(game.get_screen_width(), 1234);
+        assert_eq!(game.screen_buffer.len(), 1234 * 777 * 4);
+        // The whole screen is repainted, the board in the middle with 7.77 pixels per cell
+        assert_eq!(&game.get_dirty_rects()[..4], &[0, 0, 1234, 777]);
+        assert_eq!(game.board.cell_rect(0, 0), Some((228, 0, 8, 8)));
+        assert_eq!(game.board.cell_rect(99, 99), Some((997, 769, 8, 8)));
+    }
+
+    #[test]
+    fn game_renders_to_screens_smaller_than_a_cell() {
+        for (width, height) in [(0, 0), (3, 3)] {
+            let mut game = GameWasm::new(width, height);
+            game.render();
+            game.resize(width + 1, height);
+            game.render();
+            game.load_tileset(vec![255; 8 * 14 * 4], 8, 2).unwrap();
+            game.render();
+            game.resize(0, 0);
+            game.render();
+            assert_eq!(game.screen_buffer.len(), 0);
+        }
+    }
+
+    #[test]
+    fn game_moves_particles_while_replaying() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_player_controller(ControllerKind::Greedy);
+        game.reset_with_seed(4);
+        game.run_until_game_over(2400);
+        let replay = game.get_replay().clone();
+
+        // Bursts and sparkles of eaten food fade out within a second
+        let (mut last_score, mut eaten_at, mut faded) = (0, 0, 0);
+        let mut playback = GameWasm::new(100, 100);
+        playback
+            .play_replay_with(&replay, |game| {
+                if game.score != last_score {
+                    (last_score, eaten_at) = (game.score, game.ticks);
+                    assert!(!game.particles.is_empty());
+                } else if game.ticks - eaten_at > 120 && eaten_at > 0 {
+                    assert!(game.particles.is_empty());
+                    faded += 1;
+                }
+            })
+            .unwrap();
+        assert!(last_score > 0 && faded > 0);
+    }
+
+    #[test]
+    fn game_follows_the_snake_on_huge_levels() {
+        let mut game = GameWasm::new(800, 600);
+        let level: Vec<u8> = (0..500).flat_map(|_| [b' '; 500].into_iter().chain([b'\n'])).collect();
+        game.add_level("Huge", &level).unwrap();
+        game.load_level("Huge");
+        assert_eq!(game.get_board_width(), 500);
+        assert_eq!(game.get_player().get_head_pos(), (250, 250));
+
+        game.render();
+        // 200x150 cells of 4 pixels are visible, with the head at the right end of the dead zone
+        let viewport = game.board.get_viewport();
+        assert_eq!((viewport.get_columns(), viewport.get_rows()), (200, 150));
+        assert_eq!(viewport.get_position(), (100, 138));
+        assert!(game.get_dirty_rects().chunks(4).any(|rect| rect == [674, 474, 120, 120]));
+
+        game.key_down("Space");
+        for _ in 0..600 {
+            game.update(TICK_MILLIS);
+        }
+        game.render();
+        let (head_x, head_y) = game.get_player().get_head_pos();
+        assert!(head_x > 250);
+        assert_eq!(game.board.get_viewport().get_position(), (head_x - 150, 138));
+        assert!(game.board.cell_rect(head_x, head_y).is_some());
+    }
+
+    #[test]
+    fn game_exports_thumbnails_and_replay_clips() {
+        let mut game = GameWasm::new(100, 100);
+        let thumbnail = game.level_thumbnail_png("Board 2", 50).unwrap();
+        assert_eq!(&thumbnail[1..4], b"PNG");
+        assert!(game.level_thumbnail_png("Board 42", 50).is_err());
+
+        game.reset_with_seed(5);
+        for _ in 0..100 {
+            game.update(TICK_MILLIS);
+        }
+        let replay = game.get_replay().clone();
+        let gif = game.replay_gif(&replay, 60, 40, 25).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(gif.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (60, 40));
+        let mut frames = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            frames += 1;
+        }
+        // Ticks 1, 26, 51 and 76, then the last one
+        assert_eq!(frames, 5);
+        assert_eq!(&game.screenshot_png().unwrap()[1..4], b"PNG");
+    }
+
+    #[test]
+    fn game_queues_events_with_payloads() {
+        let mut game = GameWasm::new(100, 100);
+        game.load_level("Board 3");
+        let events = game.take_events();
+        assert_eq!(events[0], Event::LevelLoaded { name: "Board 3".to_string(), width: 100, height: 100 });
+        assert_eq!(events[1], Event::GamePause);
+
+        game.key_down("Space");
+        let (x, y) = game.get_player().get_head_pos();
+        game.food_manager.add_food(x + 1, y);
+        while game.get_score() == 0 {
+            game.tick();
+        }
+        let events = game.poll_events();
+        let kinds: Vec<GameEvent> = events.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            [GameEvent::GameStart, GameEvent::EatFood, GameEvent::AchievementUnlocked, GameEvent::SpeedChanged]
+        );
+        assert_eq!((events[1].x, events[1].y, events[1].points), (x + 1, y, FOOD_POINTS));
+        assert_eq!((events[2].achievement.as_str(), events[2].achievement_name.as_str()), ("first-bite", "First bite"));
+        assert_eq!(events[3].speed, game.get_player().get_speed());
+        assert!(game.poll_events().is_empty());
+        assert_eq!(game.get_combo_multiplier(), 2);
+        assert!(game.get_combo_millis_left() > 2900.0);
+    }
+
+    #[test]
+    fn game_tells_observers_about_events() {
+        let mut game = GameWasm::new(100, 100);
+        let seen = Rc::new(RefCell::new(Vec::new()));
+        let recorder = seen.clone();
+        let handle = game.subscribe(&[GameEvent::GameStart, GameEvent::GameOver], move |event: &Event| {
+            recorder.borrow_mut().push(event.kind());
+        });
+        game.key_down("Space");
+        game.run_until_game_over(1_000_000);
+        assert_eq!(*seen.borrow(), [GameEvent::GameStart, GameEvent::GameOver]);
+        assert_eq!(game.get_death_cause(), Some(DeathCause::HitWall { x: 99, y: 50 }));
+        assert_eq!(game.get_death_cause_name().as_deref(), Some("wall"));
+
+        // The queue for the host still has every event
+        let events = game.take_events();
+        assert_eq!(events.first(), Some(&Event::GameStart));
+        assert!(matches!(events.last(), Some(Event::GameOver { .. })));
+        assert!(game.unsubscribe(handle));
+        game.key_down("Space");
+        assert_eq!(seen.borrow().len(), 2);
+    }
+
+    #[test]
+    fn game_verifies_claimed_scores() {
+        let mut game = GameWasm::new(100, 100);
+        game.load_level("Board 2");
+        game.reset_with_seed(11);
+        play_some_game(&mut game);
+        assert!(game.get_game_state() == GameState::GameOver);
+        let honest = ScoreClaim {
+            replay: game.get_replay().clone(),
+            mode: game.get_mode(),
+            score: game.get_score(),
+            length: game.get_snake_length(),
+        };
+
+        let mut server = GameWasm::new(10, 10);
+        assert_eq!(server.verify_claim(&honest), Ok(()));
+        let higher = ScoreClaim { score: honest.score + 10, ..honest.clone() };
+        assert_eq!(
+            server.verify_claim(&higher),
+            Err(Rejection::ScoreMismatch { claimed: honest.score + 10, replayed: honest.score })
+        );
+        let longer = ScoreClaim { length: honest.length + 2, ..honest.clone() };
+        assert!(matches!(server.verify_claim(&longer), Err(Rejection::LengthMismatch { .. })));
+
+        let mut survived = honest.clone();
+        survived.replay.ticks += 100;
+        let death = honest.replay.ticks;
+        assert_eq!(server.verify_claim(&survived), Err(Rejection::EndedEarly { tick: death }));
+        let mut elsewhere = honest.clone();
+        elsewhere.replay.level = "Board 42".to_string();
+        assert_eq!(server.verify_claim(&elsewhere), Err(Rejection::UnknownLevel("Board 42".to_string())));
+    }
+
+    #[test]
+    fn game_tracks_run_and_lifetime_stats() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_player_controller(ControllerKind::Greedy);
+        let food = Rc::new(RefCell::new(0));
+        let counter = food.clone();
+        game.subscribe(&[GameEvent::EatFood], move |_: &Event| *counter.borrow_mut() += 1);
+        game.reset_with_seed(2);
+        for _ in 0..50 {
+            game.tick();
+        }
+        game.key_down("Space");
+        game.update(250.0);
+        game.key_down("Space");
+        game.run_until_game_over(2400);
+        // Taking over and turning left four times runs the long snake into itself
+        game.set_player_controller(ControllerKind::Keyboard);
+        for _ in 0..4 {
+            game.press(Action::TurnLeft);
+        }
+        game.run_until_game_over(3000);
+        assert!(game.get_game_state() == GameState::GameOver);
+
+        let stats = game.get_run_stats();
+        let player = game.get_player();
+        assert!(stats.food_eaten > 0);
+        assert_eq!(stats.food_eaten, *food.borrow());
+        assert_eq!(stats.distance as usize, player.get_moves());
+        assert_eq!(stats.max_length, player.len());
+        assert_eq!(stats.max_speed, player.get_speed());
+        assert_eq!(stats.time_alive_millis, game.get_ticks() as f64 * TICK_MILLIS as f64);
+        assert_eq!(stats.paused_millis, 250.0);
+        assert!(stats.turns > 0 && stats.near_misses <= stats.turns);
+
+        // The finished game counts once, the next one when it's abandoned
+        let lifetime = game.get_lifetime_stats();
+        assert_eq!((lifetime.games_played, lifetime.distance), (1, stats.distance));
+        game.reset_with_seed(3);
+        assert_eq!(game.get_lifetime_stats().games_played, 1);
+        game.tick();
+        game.load_level("Board 2");
+        assert_eq!(game.get_lifetime_stats().games_played, 2);
+
+        let mut other = GameWasm::new(100, 100);
+        other.load_lifetime_stats(&game.get_lifetime_stats_text()).unwrap();
+        assert_eq!(other.get_lifetime_stats(), game.get_lifetime_stats());
+
+        // Bot games don't add to the totals
+        game.set_player_controller(ControllerKind::Greedy);
+        game.reset_with_seed(4);
+        game.run_until_game_over(600);
+        game.reset_with_seed(5);
+        assert_eq!(game.get_lifetime_stats(), other.get_lifetime_stats());
+    }
+
+    #[test]
+    fn game_unlocks_achievements_for_players_only() {
+        let mut bot_game = GameWasm::new(100, 100);
+        bot_game.set_player_controller(ControllerKind::Greedy);
+        bot_game.reset_with_seed(1);
+        bot_game.run_until_game_over(5000);
+        assert!(bot_game.get_run_stats().food_eaten > 0);
+        assert!(bot_game.get_achievements().iter().all(|info| !info.unlocked));
+
+        let mut game = GameWasm::new(100, 100);
+        game.add_achievement(Achievement::new("stroll", "Stroll", "Move 20 cells", Condition::AtLeast(Stat::Distance, 20.0)));
+        game.reset_with_seed(1);
+        for _ in 0..1000 {
+            game.tick();
+        }
+        let unlocked: Vec<Event> = game
+            .take_events()
+            .into_iter()
+            .filter(|event| event.kind() == GameEvent::AchievementUnlocked)
+            .collect();
+        assert_eq!(unlocked, [Event::AchievementUnlocked { id: "stroll".to_string(), name: "Stroll".to_string() }]);
+
+        let text = game.get_achievements_text();
+        let mut other = GameWasm::new(100, 100);
+        other.load_achievements(&text).unwrap();
+        assert_eq!(other.get_achievements_text(), text);
+    }
+
+    #[test]
+    fn game_uses_rebindable_keys() {
+        let mut game = GameWasm::new(100, 100);
+        assert!(game.set_key_scheme("qwertz").is_err());
+        game.set_key_scheme("wasd").unwrap();
+        game.key_down("Space");
+        game.key_down("ArrowUp");
+        game.key_down("KeyW");
+        game.key_down("KeyX");
+        assert_eq!(game.get_replay().inputs, [(0, Key::ArrowUp)]);
+
+        game.key_down("Escape");
+        assert!(game.get_game_state() == GameState::Paused);
+        game.key_down("Escape");
+        assert!(game.get_game_state() == GameState::Paused);
+        let kinds: Vec<GameEvent> = game.take_events().iter().map(Event::kind).collect();
+        assert_eq!(
+            kinds,
+            [GameEvent::GameStart, GameEvent::GamePause, GameEvent::MenuRequested, GameEvent::MenuRequested]
+        );
+
+        game.tick();
+        game.bind_key("Enter", Action::Restart);
+        game.key_down("Enter");
+        assert!(game.get_game_state() == GameState::Running);
+        assert_eq!(game.get_ticks(), 0);
+        assert_eq!(game.get_keys_for(Action::Restart), ["KeyR", "Enter"]);
+
+        let mut other = GameWasm::new(100, 100);
+        other.load_key_bindings(&game.get_key_bindings()).unwrap();
+        assert!(other.unbind_key("Space"));
+        other.key_down("Space");
+        assert!(other.get_game_state() == GameState::Paused);
+    }
+
+    #[test]
+    fn game_turns_relative_to_the_heading_and_replays_it() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_key_scheme("relative").unwrap();
+        game.reset_with_seed(8);
+        let (x, y) = game.get_player().get_head_pos();
+        game.key_down("ArrowLeft");
+        game.key_down("ArrowUp");
+        while game.get_player().get_moves() < 3 {
+            game.tick();
+        }
+        // Heading right, a left turn goes up
+        assert_eq!(game.get_player().get_head_pos(), (x, y - 3));
+        game.press(Action::TurnRight);
+        while game.get_player().get_moves() < 4 {
+            game.tick();
+        }
+        assert_eq!(game.get_player().get_direction(), Direction::Right);
+        assert_eq!(game.get_replay().inputs.iter().map(|&(_, key)| key).collect::<Vec<_>>(), [Key::TurnLeft, Key::TurnRight]);
+
+        let replay: Replay = game.get_replay().to_string().parse().unwrap();
+        let claim = ScoreClaim { replay, mode: game.get_mode(), score: game.get_score(), length: game.get_snake_length() };
+        let mut playback = GameWasm::new(100, 100);
+        assert_eq!(playback.verify_claim(&claim), Ok(()));
+        assert_eq!(playback.get_player().get_head_pos(), game.get_player().get_head_pos());
+    }
+
+    #[test]
+    fn game_lets_the_autopilot_fill_the_board() {
+        let mut game = GameWasm::new(100, 100);
+        let level = "########\n#      #\n#      #\n#      #\n#      #\n#      #\n#      #\n########\n";
+        game.add_level("Box", level.as_bytes()).unwrap();
+        game.load_level("Box");
+        game.set_player_controller(ControllerKind::Autopilot);
+        game.reset_with_seed(1);
+        game.run_until_game_over(20_000);
+        assert!(game.get_game_state() == GameState::Running);
+        assert_eq!(game.get_death_cause(), None);
+        assert_eq!(game.get_snake_length(), 36);
+    }
+
+    #[test]
+    fn game_replays_the_player_bot() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_player_controller(ControllerKind::Greedy);
+        game.reset_with_seed(4);
+        game.run_until_game_over(2400);
+        assert!(game.get_score() > 0);
+        assert_eq!(game.submit_high_score("Bot", 1000.0, true), Some(0));
+        let replay = game.get_high_scores("Board 1", "greedy")[0].replay.clone().unwrap();
+        assert_eq!(replay.player, ControllerKind::Greedy);
+
+        let mut playback = GameWasm::new(100, 100);
+        playback.play_replay(&replay).unwrap();
+        assert_eq!(playback.get_ticks(), 2400);
+        assert_eq!(playback.get_score(), game.get_score());
+        assert_eq!(playback.get_player().get_head_pos(), game.get_player().get_head_pos());
+
+        // Taking over from the bot plays a game the replay can't repeat
+        game.set_player_controller(ControllerKind::Keyboard);
+        assert_eq!(game.submit_high_score("Me", 2000.0, true), Some(0));
+        assert!(game.get_high_scores("Board 1", "classic")[0].replay.is_none());
+    }
+
+    #[test]
+    fn game_verifies_bot_high_scores() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_player_controller(ControllerKind::Greedy);
+        game.add_computer_snake(ControllerKind::Survival);
+        game.reset_with_seed(8);
+        game.run_until_game_over(2400);
+        assert_eq!(game.submit_high_score("Bot", 1000.0, true), Some(0));
+        let entry = &game.get_high_scores("Board 1", "greedy vs-survival")[0];
+
+        let claim = ScoreClaim::from_high_score("greedy vs-survival", entry).unwrap();
+        let mut server = GameWasm::new(10, 10);
+        assert_eq!(server.verify_claim(&claim), Ok(()));
+        let classic = ScoreClaim { mode: "classic".to_string(), ..claim };
+        assert_eq!(
+            server.verify_claim(&classic),
+            Err(Rejection::ModeMismatch { claimed: "classic".to_string(), replayed: "greedy vs-survival".to_string() })
+        );
+    }
+
+    #[test]
+    fn game_keeps_high_scores_across_resets() {
+        let mut game = GameWasm::new(100, 100);
+        game.set_player_controller(ControllerKind::Greedy);
+        game.reset_with_seed(4);
+        game.run_until_game_over(2400);
+        assert_eq!(game.get_mode(), "greedy");
+        assert!(game.is_high_score());
+        assert_eq!(game.submit_high_score("Bot", 1000.0, true), Some(0));
+        let (score, ticks, replay) = (game.get_score(), game.get_ticks(), game.get_replay().clone());
+
+        game.reset_with_seed(5);
+        game.add_computer_snake(ControllerKind::Survival);
+        game.load_level("Board 2");
+        assert_eq!(game.get_mode(), "greedy vs-survival");
+        let entries = game.get_high_scores("Board 1", "greedy");
+        assert_eq!((entries[0].name.as_str(), entries[0].score, entries[0].seed), ("Bot", score, 4));
+        assert_eq!(entries[0].duration_millis, (ticks as f32 * TICK_MILLIS).round() as u32);
+
+        let mut other = GameWasm::new(100, 100);
+        other.load_high_scores(&game.get_high_scores_text()).unwrap();
+        other.load_high_scores(&game.get_high_scores_text()).unwrap();
+        assert_eq!(other.get_high_scores("Board 1", "greedy").len(), 1);
+        let entry = &other.get_high_scores("Board 1", "greedy")[0];
+        assert_eq!(entry.get_replay_text(), Some(replay.to_string()));
+        assert!(other.load_high_scores("nonsense").is_err());
+    }
 }
@@ -0,0 +1,251 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use indexmap::IndexMap;
+use crate::replay::Replay;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const HEADER: &str = "snake-highscores 1";
+const FIELDS: usize = 9;
+
+/// How many entries each table keeps unless told otherwise.
+pub const DEFAULT_TABLE_SIZE: usize = 10;
+
+/// A finished game in a high-score table.
+#[derive(Clone, Debug, PartialEq)]
+#[wasm_bindgen(getter_with_clone)]
+pub struct HighScore {
+    pub name: String,
+    pub score: u32,
+    pub length: usize,
+    pub duration_millis: u32,
+    /// When the game was played in milliseconds since 1970, as the host's clock tells.
+    pub date: f64,
+    pub seed: u64,
+    /// The recording of the game, so the score can be checked by playing it again.
+    #[wasm_bindgen(skip)]
+    pub replay: Option<Replay>,
+}
+
+#[wasm_bindgen]
+impl HighScore {
+    /// The replay in the format `Replay` reads, if the entry kept one.
+    #[wasm_bindgen]
+    pub fn get_replay_text(&self) -> Option<String> {
+        self.replay.as_ref().map(Replay::to_string)
+    }
+}
+
+/// The best games of every level and mode, best first. Of equal scores the older one stays
+/// ahead.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HighScores {
+    size: usize,
+    tables: IndexMap<(String, String), Vec<HighScore>>,
+}
+
+impl HighScores {
+    /// Keeps the best `size` games of every table.
+    pub fn new(size: usize) -> Self {
+        HighScores { size, tables: IndexMap::new() }
+    }
+
+    /// The entries of a table, best first.
+    pub fn get(&self, level: &str, mode: &str) -> &[HighScore] {
+        self.tables
+            .get(&(level.to_string(), mode.to_string()))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Whether a game with this score would make it into the table.
+    pub fn qualifies(&self, level: &str, mode: &str, score: u32) -> bool {
+        let entries = self.get(level, mode);
+        score > 0 && (entries.len() < self.size || entries.last().is_some_and(|last| score > last.score))
+    }
+
+    /// Puts a game into its table. Returns its rank counted from 0, or `None` if it wasn't good
+    /// enough.
+    pub fn add(&mut self, level: &str, mode: &str, entry: HighScore) -> Option<usize> {
+        if !self.qualifies(level, mode, entry.score) {
+            return None;
+        }
+        let entries = self.tables.entry((level.to_string(), mode.to_string())).or_default();
+        let rank = entries.partition_point(|other| other.score >= entry.score);
+        entries.insert(rank, entry);
+        entries.truncate(self.size);
+        Some(rank)
+    }
+
+    /// Adds every entry of another table, e.g. one loaded from storage.
+    pub fn merge(&mut self, other: HighScores) {
+        for ((level, mode), entries) in other.tables {
+            for entry in entries {
+                self.add(&level, &mode, entry);
+            }
+        }
+    }
+
+    /// Replaces the tables with ones written by `Display`, keeping the best `size` games of each.
+    pub fn load(&mut self, text: &str) -> Result<(), String> {
+        let loaded = text.parse()?;
+        self.clear();
+        self.merge(loaded);
+        Ok(())
+    }
+
+    pub fn clear(&mut self) {
+        self.tables.clear();
+    }
+}
+
+/// Tabs and line breaks separate fields and entries, so they are escaped inside of fields.
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => result.push('\t'),
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => break,
+        }
+    }
+    result
+}
+
+/// Writes one entry per line as tab separated `level mode name score length duration date seed
+/// replay` fields, the replay being empty if the entry has none.
+impl Display for HighScores {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for ((level, mode), entries) in &self.tables {
+            for entry in entries {
+                let replay = entry.replay.as_ref().map(Replay::to_string).unwrap_or_default();
+                writeln!(
+                    f,
+                    "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                    escape(level),
+                    escape(mode),
+                    escape(&entry.name),
+                    entry.score,
+                    entry.length,
+                    entry.duration_millis,
+                    entry.date,
+                    entry.seed,
+                    escape(&replay)
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads tables of any size, entries that don't fit are dropped when the result is added to a
+/// smaller table.
+impl FromStr for HighScores {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        if lines.next().map(str::trim) != Some(HEADER) {
+            return Err("Not a high-score table, missing header".to_string());
+        }
+
+        let mut high_scores = HighScores::new(usize::MAX);
+        for line in lines {
+            let fields: Vec<String> = line.split('\t').map(unescape).collect();
+            if fields.len() != FIELDS {
+                return Err(format!("Invalid high-score line: {line}"));
+            }
+            let invalid = || format!("Invalid number in line: {line}");
+            let replay = match fields[8].as_str() {
+                "" => None,
+                text => Some(text.parse::<Replay>()?),
+            };
+            let entry = HighScore {
+                name: fields[2].clone(),
+                score: fields[3].parse().map_err(|_| invalid())?,
+                length: fields[4].parse().map_err(|_| invalid())?,
+                duration_millis: fields[5].parse().map_err(|_| invalid())?,
+                date: fields[6].parse().map_err(|_| invalid())?,
+                seed: fields[7].parse().map_err(|_| invalid())?,
+                replay,
+            };
+            high_scores.add(&fields[0], &fields[1], entry);
+        }
+        Ok(high_scores)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Key;
+
+    fn entry(name: &str, score: u32) -> HighScore {
+        HighScore {
+            name: name.to_string(),
+            score,
+            length: 12,
+            duration_millis: 34_000,
+            date: 1_760_000_000_000.0,
+            seed: 99,
+            replay: None,
+        }
+    }
+
+    #[test]
+    fn high_scores_keep_the_best_entries() {
+        let mut high_scores = HighScores::new(3);
+        assert_eq!(high_scores.add("Board 1", "classic", entry("a", 30)), Some(0));
+        assert_eq!(high_scores.add("Board 1", "classic", entry("b", 50)), Some(0));
+        assert_eq!(high_scores.add("Board 1", "classic", entry("c", 30)), Some(2));
+        assert_eq!(high_scores.add("Board 1", "classic", entry("d", 20)), None);
+        assert_eq!(high_scores.add("Board 1", "classic", entry("e", 40)), Some(1));
+        assert_eq!(high_scores.add("Board 1", "classic", entry("f", 0)), None);
+
+        let names: Vec<&str> = high_scores.get("Board 1", "classic").iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["b", "e", "a"]);
+        assert!(high_scores.get("Board 1", "vs-greedy").is_empty());
+        assert!(high_scores.qualifies("Board 2", "classic", 1));
+        assert!(!high_scores.qualifies("Board 1", "classic", 30));
+    }
+
+    #[test]
+    fn high_scores_round_trip_through_text() {
+        let mut replay = Replay::new("Board 2", 99, Vec::new());
+        replay.record(10, Key::ArrowUp);
+        replay.ticks = 500;
+        let mut high_scores = HighScores::new(DEFAULT_TABLE_SIZE);
+        high_scores.add("Board 2", "classic", HighScore { replay: Some(replay), ..entry("Tab\tand \\ slash", 70) });
+        high_scores.add("Board 2", "vs-greedy", entry("b", 20));
+
+        let text = high_scores.to_string();
+        assert_eq!(text.lines().count(), 3);
+        let mut loaded = HighScores::new(DEFAULT_TABLE_SIZE);
+        loaded.load(&text).unwrap();
+        assert_eq!(loaded, high_scores);
+        loaded.load(&text).unwrap();
+        assert_eq!(loaded, high_scores);
+        assert!(loaded.load("snake-highscores 2").is_err());
+        assert_eq!(loaded, high_scores);
+        let replay_text = loaded.get("Board 2", "classic")[0].get_replay_text().unwrap();
+        assert!(replay_text.contains("input 10 ArrowUp"));
+    }
+
+    #[test]
+    fn high_scores_reject_invalid_text() {
+        assert!("".parse::<HighScores>().is_err());
+        assert!("snake-highscores 1\nBoard 1\tclassic\ta\t10".parse::<HighScores>().is_err());
+        assert!("snake-highscores 1\nBoard 1\tclassic\ta\tten\t1\t1\t1\t1\t".parse::<HighScores>().is_err());
+        assert!("snake-highscores 1\nBoard 1\tclassic\ta\t10\t1\t1\t1\t1\tnot a replay".parse::<HighScores>().is_err());
+        assert!("snake-highscores 1\nBoard 1\tclassic\ta\t10\t1\t1\t1\t1\t\n".parse::<HighScores>().is_ok());
+    }
+}
@@ -0,0 +1,226 @@
+use crate::canvas::{Canvas, Rect};
+use crate::snake::DeathCause;
+use crate::{Color, GameState};
+
+pub(crate) const GLYPH_WIDTH: usize = 5;
+const GLYPH_HEIGHT: usize = 7;
+/// Rows of a 5x7 glyph from top to bottom, the highest of the five bits is the leftmost pixel.
+pub(crate) type Glyph = [u8; GLYPH_HEIGHT];
+
+const FONT: [(char, Glyph); 46] = [
+    (' ', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b00000]),
+    ('0', [0b01110, 0b10001, 0b10011, 0b10101, 0b11001, 0b10001, 0b01110]),
+    ('1', [0b00100, 0b01100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('2', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b01000, 0b11111]),
+    ('3', [0b11111, 0b00010, 0b00100, 0b00010, 0b00001, 0b10001, 0b01110]),
+    ('4', [0b00010, 0b00110, 0b01010, 0b10010, 0b11111, 0b00010, 0b00010]),
+    ('5', [0b11111, 0b10000, 0b11110, 0b00001, 0b00001, 0b10001, 0b01110]),
+    ('6', [0b00110, 0b01000, 0b10000, 0b11110, 0b10001, 0b10001, 0b01110]),
+    ('7', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b01000, 0b01000]),
+    ('8', [0b01110, 0b10001, 0b10001, 0b01110, 0b10001, 0b10001, 0b01110]),
+    ('9', [0b01110, 0b10001, 0b10001, 0b01111, 0b00001, 0b00010, 0b01100]),
+    ('A', [0b01110, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('B', [0b11110, 0b10001, 0b10001, 0b11110, 0b10001, 0b10001, 0b11110]),
+    ('C', [0b01110, 0b10001, 0b10000, 0b10000, 0b10000, 0b10001, 0b01110]),
+    ('D', [0b11100, 0b10010, 0b10001, 0b10001, 0b10001, 0b10010, 0b11100]),
+    ('E', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b11111]),
+    ('F', [0b11111, 0b10000, 0b10000, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('G', [0b01110, 0b10001, 0b10000, 0b10111, 0b10001, 0b10001, 0b01111]),
+    ('H', [0b10001, 0b10001, 0b10001, 0b11111, 0b10001, 0b10001, 0b10001]),
+    ('I', [0b01110, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b01110]),
+    ('J', [0b00111, 0b00010, 0b00010, 0b00010, 0b00010, 0b10010, 0b01100]),
+    ('K', [0b10001, 0b10010, 0b10100, 0b11000, 0b10100, 0b10010, 0b10001]),
+    ('L', [0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b10000, 0b11111]),
+    ('M', [0b10001, 0b11011, 0b10101, 0b10101, 0b10001, 0b10001, 0b10001]),
+    ('N', [0b10001, 0b10001, 0b11001, 0b10101, 0b10011, 0b10001, 0b10001]),
+    ('O', [0b01110, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('P', [0b11110, 0b10001, 0b10001, 0b11110, 0b10000, 0b10000, 0b10000]),
+    ('Q', [0b01110, 0b10001, 0b10001, 0b10001, 0b10101, 0b10010, 0b01101]),
+    ('R', [0b11110, 0b10001, 0b10001, 0b11110, 0b10100, 0b10010, 0b10001]),
+    ('S', [0b01111, 0b10000, 0b10000, 0b01110, 0b00001, 0b00001, 0b11110]),
+    ('T', [0b11111, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00100]),
+    ('U', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01110]),
+    ('V', [0b10001, 0b10001, 0b10001, 0b10001, 0b10001, 0b01010, 0b00100]),
+    ('W', [0b10001, 0b10001, 0b10001, 0b10101, 0b10101, 0b10101, 0b01010]),
+    ('X', [0b10001, 0b10001, 0b01010, 0b00100, 0b01010, 0b10001, 0b10001]),
+    ('Y', [0b10001, 0b10001, 0b10001, 0b01010, 0b00100, 0b00100, 0b00100]),
+    ('Z', [0b11111, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b11111]),
+    ('.', [0b00000, 0b00000, 0b00000, 0b00000, 0b00000, 0b01100, 0b01100]),
+    (':', [0b00000, 0b01100, 0b01100, 0b00000, 0b01100, 0b01100, 0b00000]),
+    ('-', [0b00000, 0b00000, 0b00000, 0b11111, 0b00000, 0b00000, 0b00000]),
+    ('+', [0b00000, 0b00100, 0b00100, 0b11111, 0b00100, 0b00100, 0b00000]),
+    ('!', [0b00100, 0b00100, 0b00100, 0b00100, 0b00100, 0b00000, 0b00100]),
+    ('/', [0b00000, 0b00001, 0b00010, 0b00100, 0b01000, 0b10000, 0b00000]),
+    ('%', [0b11000, 0b11001, 0b00010, 0b00100, 0b01000, 0b10011, 0b00011]),
+    ('#', [0b01010, 0b01010, 0b11111, 0b01010, 0b11111, 0b01010, 0b01010]),
+    ('?', [0b01110, 0b10001, 0b00001, 0b00010, 0b00100, 0b00000, 0b00100]),
+];
+
+const TEXT_COLOR: Color = Color { r: 255, g: 255, b: 0, a: 255 };
+const GAME_OVER_COLOR: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+const START_COLOR: Color = Color { r: 144, g: 238, b: 144, a: 255 };
+/// Screen pixels per font pixel for every `SCALE_STEP` pixels of the shorter screen side.
+const SCALE_STEP: usize = 400;
+const BANNER_SCALE: usize = 3;
+
+/// What the HUD shows.
+pub struct HudInfo<'a> {
+    pub level_name: &'a str,
+    pub score: u32,
+    pub length: usize,
+    pub speed: f32,
+    pub state: GameState,
+    pub started: bool,
+    pub death_cause: Option<DeathCause>,
+    /// The combo multiplier for the next piece of food.
+    pub multiplier: u32,
+    /// How much of the combo time is left, from 1 down to 0.
+    pub combo_left: f32,
+}
+
+/// Draws the status line at the top of the screen and a banner in the middle while the game
+/// isn't running. A running combo shows next to the score, with a bar below it that shrinks
+/// until the combo runs out. Returns the rectangles it drew over.
+pub fn render_hud(canvas: &mut Canvas, info: &HudInfo) -> Vec<Rect> {
+    let scale = (canvas.get_width().min(canvas.get_height()) / SCALE_STEP).max(1);
+    let margin = 4 * scale;
+    let mut rects = Vec::new();
+
+    let status = format!("{}  LENGTH {}  SPEED {:.1}", info.level_name, info.length, info.speed);
+    rects.push(canvas.draw_text(margin, margin, scale, &status, TEXT_COLOR));
+    let mut score = format!("SCORE {}", info.score);
+    if info.multiplier > 1 {
+        score.push_str(&format!("  X{}", info.multiplier));
+    }
+    let (score_width, score_height) = text_size(&score, scale);
+    let score_left = canvas.get_width().saturating_sub(score_width + margin);
+    rects.push(canvas.draw_text(score_left, margin, scale, &score, TEXT_COLOR));
+    if info.multiplier > 1 {
+        let bar_width = (score_width as f32 * info.combo_left.clamp(0.0, 1.0)).round() as usize;
+        let bar = (score_left + score_width - bar_width, margin + score_height + scale, bar_width, scale);
+        rects.push(canvas.blend_rect(bar, START_COLOR, 255));
+    }
+
+    let banner = match info.state {
+        GameState::Running => None,
+        GameState::GameOver => Some(("GAME OVER", GAME_OVER_COLOR)),
+        GameState::Paused if info.started => Some(("PAUSED", TEXT_COLOR)),
+        GameState::Paused => Some(("PRESS SPACE TO START", START_COLOR)),
+    };
+    if let Some((text, color)) = banner {
+        let banner_scale = scale * BANNER_SCALE;
+        let (width, height) = text_size(text, banner_scale);
+        let left = canvas.get_width().saturating_sub(width) / 2;
+        let top = canvas.get_height().saturating_sub(height) / 2;
+        let padding = 2 * banner_scale;
+        let box_left = left.saturating_sub(padding);
+        let box_top = top.saturating_sub(padding);
+        rects.push(canvas.darken((box_left, box_top, width + 2 * padding, height + 2 * padding)));
+        canvas.draw_text(left, top, banner_scale, text, color);
+
+        // Tell the player why the run ended, right below the banner
+        if let (GameState::GameOver, Some(cause)) = (info.state, info.death_cause) {
+            let cause = cause.to_string();
+            let (cause_width, _) = text_size(&cause, scale);
+            let cause_left = canvas.get_width().saturating_sub(cause_width) / 2;
+            rects.push(canvas.draw_text(cause_left, top + height + 2 * padding, scale, &cause, TEXT_COLOR));
+        }
+    }
+    rects
+}
+
+/// The pixel size of `text` drawn at `scale`.
+pub fn text_size(text: &str, scale: usize) -> (usize, usize) {
+    let chars = text.chars().count();
+    let width = (chars * (GLYPH_WIDTH + 1)).saturating_sub(1) * scale;
+    (width, GLYPH_HEIGHT * scale)
+}
+
+pub(crate) fn glyph(char: char) -> &'static Glyph {
+    let char = char.to_ascii_uppercase();
+    FONT.iter()
+        .find(|(font_char, _)| *font_char == char)
+        .or_else(|| FONT.iter().find(|(font_char, _)| *font_char == '?'))
+        .map(|(_, glyph)| glyph)
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hud_draws_text_pixels() {
+        let mut buffer = vec![0; 20 * 10 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 20, 10);
+        let rect = canvas.draw_text(1, 1, 1, "i1", TEXT_COLOR);
+        assert_eq!(rect, (1, 1, 11, 7));
+
+        let lit = |x: usize, y: usize| buffer[(y * 20 + x) * 4] == 255;
+        // The I has a bar at the top, the 1 a flag left of its stem
+        assert!(lit(2, 1) && lit(3, 1) && lit(4, 1));
+        assert!(!lit(1, 1) && !lit(5, 2));
+        assert!(lit(8, 2) && lit(9, 1) && !lit(8, 1));
+    }
+
+    #[test]
+    fn hud_clips_text_to_the_canvas() {
+        let mut buffer = vec![0; 8 * 4 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 8, 4);
+        assert_eq!(canvas.draw_text(4, 2, 2, "W?", TEXT_COLOR), (4, 2, 4, 2));
+        assert_eq!(canvas.draw_text(10, 10, 1, "X", TEXT_COLOR), (8, 4, 0, 0));
+    }
+
+    #[test]
+    fn hud_shows_banner_only_when_not_running() {
+        let mut buffer = vec![0; 400 * 300 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 400, 300);
+        let mut info = HudInfo {
+            level_name: "Board 1",
+            score: 120,
+            length: 9,
+            speed: 5.25,
+            state: GameState::Running,
+            started: true,
+            death_cause: None,
+            multiplier: 1,
+            combo_left: 0.0,
+        };
+        assert_eq!(render_hud(&mut canvas, &info).len(), 2);
+        info.state = GameState::GameOver;
+        let rects = render_hud(&mut canvas, &info);
+        assert_eq!(rects.len(), 3);
+
+        // The 159x21 pixels of text are centred with some padding around them
+        assert_eq!(text_size("GAME OVER", 3), (159, 21));
+        assert_eq!(rects[2], (114, 133, 171, 33));
+
+        // The cause of death goes below the banner
+        info.death_cause = Some(DeathCause::HitWall { x: 1, y: 2 });
+        let rects = render_hud(&mut canvas, &info);
+        assert_eq!(text_size("HIT A WALL", 1), (59, 7));
+        assert_eq!(rects[3], (170, 172, 59, 7));
+    }
+
+    #[test]
+    fn hud_shows_the_combo_next_to_the_score() {
+        let mut buffer = vec![0; 400 * 300 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 400, 300);
+        let info = HudInfo {
+            level_name: "Board 1",
+            score: 80,
+            length: 20,
+            speed: 6.0,
+            state: GameState::Running,
+            started: true,
+            death_cause: None,
+            multiplier: 3,
+            combo_left: 0.5,
+        };
+        let rects = render_hud(&mut canvas, &info);
+        assert_eq!(rects.len(), 3);
+        // "SCORE 80  X3" is 71 pixels wide, half of the bar is left
+        assert_eq!(rects[1], (325, 4, 71, 7));
+        assert_eq!(rects[2], (360, 12, 36, 1));
+    }
+}
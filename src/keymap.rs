@@ -0,0 +1,233 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use indexmap::IndexMap;
+use crate::Key;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const HEADER: &str = "snake-keys 1";
+
+/// Names of the built-in key schemes, the first one is the default.
+pub const SCHEMES: [&str; 5] = ["arrows", "wasd", "hjkl", "numpad", "relative"];
+
+/// What a key does in the game.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[wasm_bindgen]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    /// Turns a quarter counter-clockwise from where the snake is heading.
+    TurnLeft,
+    /// Turns a quarter clockwise from where the snake is heading.
+    TurnRight,
+    /// Starts, pauses and resumes the game, and starts a new one after game over.
+    Pause,
+    /// Starts a new game right away.
+    Restart,
+    /// Pauses the game and asks the host to show its menu.
+    Menu,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::TurnLeft,
+        Action::TurnRight,
+        Action::Pause,
+        Action::Restart,
+        Action::Menu,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::TurnLeft => "turn-left",
+            Action::TurnRight => "turn-right",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Menu => "menu",
+        }
+    }
+
+    /// The key replays record for steering actions.
+    pub fn steering_key(self) -> Option<Key> {
+        match self {
+            Action::Up => Some(Key::ArrowUp),
+            Action::Down => Some(Key::ArrowDown),
+            Action::Left => Some(Key::ArrowLeft),
+            Action::Right => Some(Key::ArrowRight),
+            Action::TurnLeft => Some(Key::TurnLeft),
+            Action::TurnRight => Some(Key::TurnRight),
+            Action::Pause | Action::Restart | Action::Menu => None,
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Action::ALL
+            .into_iter()
+            .find(|action| action.name() == name)
+            .ok_or_else(|| format!("Unknown action: {name}"))
+    }
+}
+
+/// Which `KeyboardEvent.code` does what. Keys that aren't bound do nothing.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyMap {
+    bindings: IndexMap<String, Action>,
+}
+
+impl KeyMap {
+    /// A map without any bindings.
+    pub fn new() -> Self {
+        KeyMap { bindings: IndexMap::new() }
+    }
+
+    /// One of the schemes named in `SCHEMES`. They all pause with Space, restart with R and open
+    /// the menu with Escape. The relative scheme only turns left and right, with the left and
+    /// right arrows or A and D.
+    pub fn scheme(name: &str) -> Option<Self> {
+        let absolute = [Action::Up, Action::Down, Action::Left, Action::Right];
+        let relative = [Action::TurnLeft, Action::TurnRight, Action::TurnLeft, Action::TurnRight];
+        let (codes, actions): (&[&str], &[Action]) = match name {
+            "arrows" => (&["ArrowUp", "ArrowDown", "ArrowLeft", "ArrowRight"], &absolute),
+            "wasd" => (&["KeyW", "KeyS", "KeyA", "KeyD"], &absolute),
+            "hjkl" => (&["KeyK", "KeyJ", "KeyH", "KeyL"], &absolute),
+            "numpad" => (&["Numpad8", "Numpad2", "Numpad4", "Numpad6"], &absolute),
+            "relative" => (&["ArrowLeft", "ArrowRight", "KeyA", "KeyD"], &relative),
+            _ => return None,
+        };
+        let mut map = KeyMap::new();
+        for (code, &action) in codes.iter().zip(actions) {
+            map.bind(code, action);
+        }
+        map.bind("Space", Action::Pause);
+        map.bind("KeyR", Action::Restart);
+        map.bind("Escape", Action::Menu);
+        Some(map)
+    }
+
+    /// Makes a key do `action`, replacing what it did before. Other keys for the same action
+    /// keep working.
+    pub fn bind(&mut self, code: &str, action: Action) {
+        self.bindings.insert(code.to_string(), action);
+    }
+
+    /// Returns `false` if the key wasn't bound.
+    pub fn unbind(&mut self, code: &str) -> bool {
+        self.bindings.shift_remove(code).is_some()
+    }
+
+    pub fn action(&self, code: &str) -> Option<Action> {
+        self.bindings.get(code).copied()
+    }
+
+    /// The keys bound to an action, in the order they were bound.
+    pub fn keys_for(&self, action: Action) -> Vec<String> {
+        self.bindings
+            .iter()
+            .filter(|&(_, &bound)| bound == action)
+            .map(|(code, _)| code.clone())
+            .collect()
+    }
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::scheme(SCHEMES[0]).unwrap()
+    }
+}
+
+/// Writes the map as plain text, one `bind <code> <action>` line per key.
+impl Display for KeyMap {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        for (code, action) in &self.bindings {
+            writeln!(f, "bind {code} {}", action.name())?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for KeyMap {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err("Not a key map, missing header".to_string());
+        }
+
+        let mut map = KeyMap::new();
+        for line in lines {
+            let mut words = line.split_whitespace();
+            match (words.next(), words.next(), words.next(), words.next()) {
+                (Some("bind"), Some(code), Some(action), None) => map.bind(code, action.parse()?),
+                _ => return Err(format!("Invalid key map line: {line}")),
+            }
+        }
+        Ok(map)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_map_schemes_steer_and_share_the_other_actions() {
+        let map = KeyMap::default();
+        assert_eq!(map.action("ArrowLeft"), Some(Action::Left));
+        assert_eq!(map.action("KeyA"), None);
+        let hjkl = KeyMap::scheme("hjkl").unwrap();
+        assert_eq!(hjkl.action("KeyK"), Some(Action::Up));
+        assert_eq!(hjkl.action("ArrowUp"), None);
+        assert_eq!(KeyMap::scheme("numpad").unwrap().action("Numpad4"), Some(Action::Left));
+        for name in SCHEMES {
+            let scheme = KeyMap::scheme(name).unwrap();
+            assert_eq!(scheme.action("Space"), Some(Action::Pause));
+            assert_eq!(scheme.action("KeyR"), Some(Action::Restart));
+            assert_eq!(scheme.action("Escape"), Some(Action::Menu));
+        }
+        let relative = KeyMap::scheme("relative").unwrap();
+        assert_eq!(relative.keys_for(Action::TurnLeft), ["ArrowLeft", "KeyA"]);
+        assert_eq!(relative.action("ArrowUp"), None);
+        assert!(KeyMap::scheme("dvorak").is_none());
+    }
+
+    #[test]
+    fn key_map_rebinds_keys() {
+        let mut map = KeyMap::scheme("wasd").unwrap();
+        map.bind("ArrowUp", Action::Up);
+        map.bind("KeyR", Action::Pause);
+        assert_eq!(map.keys_for(Action::Up), ["KeyW", "ArrowUp"]);
+        assert_eq!(map.keys_for(Action::Pause), ["Space", "KeyR"]);
+        assert!(map.keys_for(Action::Restart).is_empty());
+        assert!(map.unbind("KeyW"));
+        assert!(!map.unbind("KeyW"));
+        assert_eq!(map.action("KeyW"), None);
+    }
+
+    #[test]
+    fn key_map_round_trips_through_text() {
+        let mut map = KeyMap::scheme("numpad").unwrap();
+        map.bind("Enter", Action::Restart);
+        let text = map.to_string();
+        assert!(text.starts_with("snake-keys 1\nbind Numpad8 up\n"));
+        assert_eq!(text.parse::<KeyMap>(), Ok(map));
+
+        assert!("bind KeyW up".parse::<KeyMap>().is_err());
+        assert!("snake-keys 1\nbind KeyW jump".parse::<KeyMap>().is_err());
+        assert!("snake-keys 1\nbind KeyW".parse::<KeyMap>().is_err());
+    }
+}
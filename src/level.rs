@@ -1,37 +1,54 @@
 use indexmap::IndexMap;
 
-type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+pub(crate) type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 pub type Level = Vec<u8>;
 
-pub struct LevelManager<'a> {
-    level_size: usize,
-    levels: IndexMap<&'a str, Level>,
+pub struct LevelManager {
+    width: usize,
+    height: usize,
+    levels: IndexMap<String, (usize, usize, Level)>,
 }
 
-impl<'a> LevelManager<'a> {
-    pub fn new(level_size: usize) -> Self {
+impl LevelManager {
+    /// Levels whose size can't be told from their lines are expected to be `width` x `height`.
+    pub fn new(width: usize, height: usize) -> Self {
         LevelManager {
-            level_size,
+            width,
+            height,
             levels: IndexMap::new(),
         }
     }
 
-    pub fn get_level<'b>(&self, level_name: &str) -> Option<&Level> {
-        self.levels.get(level_name)
+    pub fn get_level(&self, level_name: &str) -> Option<&Level> {
+        self.levels.get(level_name).map(|(_, _, level)| level)
     }
 
-    pub fn add_level(&mut self, level_name: &'a str, level_data: &[u8]) -> Result<()>   {
-        let level_data: Level = level_data
-            .into_iter()
-            .filter(|b| **b != b'\n' && **b != b'\r')
-            .copied()
+    /// The width and height of a level in cells.
+    pub fn get_level_size(&self, level_name: &str) -> Option<(usize, usize)> {
+        self.levels.get(level_name).map(|&(width, height, _)| (width, height))
+    }
+
+    /// Adds a level of any size written as lines of equal length. Other levels must have the
+    /// default size, their line breaks are ignored.
+    pub fn add_level(&mut self, level_name: &str, level_data: &[u8]) -> Result<()> {
+        let lines: Vec<&[u8]> = level_data
+            .split(|&b| b == b'\n')
+            .map(|line| line.strip_suffix(b"\r").unwrap_or(line))
+            .filter(|line| !line.is_empty())
             .collect();
-        let expected_size = self.level_size;
+        let level_data: Level = lines.concat();
+        let line_width = lines.first().map_or(0, |line| line.len());
+        let (width, height) = if lines.len() > 1 && lines.iter().all(|line| line.len() == line_width) {
+            (line_width, lines.len())
+        } else {
+            (self.width, self.height)
+        };
+        let expected_size = width * height;
         let got_size = level_data.len();
 
-        if expected_size == got_size {
-            self.levels.insert(level_name, level_data);
+        if expected_size == got_size && got_size > 0 {
+            self.levels.insert(level_name.to_string(), (width, height, level_data));
             Ok(())
         } else {
             Err(format!("Invalid level size. Expected: {expected_size}, got: {got_size}").into())
@@ -39,7 +56,7 @@ impl<'a> LevelManager<'a> {
     }
 
     pub(crate) fn get_level_names(&self) -> Vec<String> {
-        self.levels.keys().map(|&name| name.to_string()).collect()
+        self.levels.keys().cloned().collect()
     }
 }
 
@@ -49,7 +66,7 @@ mod tests {
 
     #[test]
     fn level_manager_adds_and_retrieves_levels() {
-        let mut manager = LevelManager::new(81);
+        let mut manager = LevelManager::new(9, 9);
         let level_data = b"##########       ## ##### ## #   # ## # # # ## #   # ## ##### ##       ##########";
         manager.add_level("level1", level_data).unwrap();
         let retrieved_level = manager.get_level("level1").unwrap();
@@ -58,7 +75,7 @@ mod tests {
 
     #[test]
     fn level_manager_can_add_level_with_line_breaks() {
-        let mut manager = LevelManager::new(81);
+        let mut manager = LevelManager::new(9, 9);
         let level_data_with_breaks = b"##########\n       ##\n ##### ##\n #   # ##\n # # # ##\n #   # ##\n ##### ##\n       ##########";
         let clean_level_data: Vec<u8> = level_data_with_breaks
             .iter()
@@ -72,14 +89,14 @@ mod tests {
 
     #[test]
     fn level_manager_returns_none_for_nonexistent_level() {
-        let manager = LevelManager::new(81);
+        let manager = LevelManager::new(9, 9);
         let retrieved_level = manager.get_level("nonexistent_level");
         assert!(retrieved_level.is_none());
     }
 
     #[test]
     fn level_manager_rejects_invalid_level_size() {
-        let mut manager = LevelManager::new(81);
+        let mut manager = LevelManager::new(9, 9);
         let invalid_level_data = b"##########       ## ##### ## #   # ## # # # ## #   # ## ##### ##       ########";
         let result = manager.add_level("level1", invalid_level_data);
         assert!(result.is_err());
@@ -87,7 +104,7 @@ mod tests {
 
     #[test]
     fn level_manager_can_determine_level_names() {
-        let mut manager = LevelManager::new(81);
+        let mut manager = LevelManager::new(9, 9);
         let level_data1 = b"##########       ## ##### ## #   # ## # # # ## #   # ## ##### ##       ##########";
         let level_data2 = b"##########       ## ##### ## #   # ## # # # ## #   # ## ##### ##       ##########";
         manager.add_level("level 1", level_data1).unwrap();
@@ -95,4 +112,13 @@ mod tests {
         let level_names = manager.get_level_names();
         assert_eq!(level_names, vec!["level 1", "level 2"]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn level_manager_reads_the_size_from_the_lines() {
+        let mut manager = LevelManager::new(9, 9);
+        manager.add_level("wide", b"#####\r\n#   #\r\n#####\r\n").unwrap();
+        assert_eq!(manager.get_level_size("wide"), Some((5, 3)));
+        assert_eq!(manager.get_level("wide").unwrap().len(), 15);
+        assert!(manager.add_level("ragged", b"#####\n#  #\n#####").is_err());
+    }
+}
@@ -1,10 +1,41 @@
+pub use achievements::{Achievement, AchievementInfo, Condition, Stat};
+pub use controller::ControllerKind;
+pub use env::{Observation, SnakeEnv};
+pub use events::{Event, EventObserver, GameEventInfo};
 pub use game::GameWasm;
+pub use keymap::{Action, KeyMap};
+pub use highscores::{HighScore, HighScores};
+pub use palette::Palette;
+pub use replay::Replay;
+pub use snake::{DeathCause, MoveOutcome};
+pub use stats::{LifetimeStats, RunStats};
+pub use verify::{Rejection, ScoreClaim};
 
+mod achievements;
+mod autopilot;
 mod board;
+mod canvas;
+mod controller;
+mod env;
+mod events;
+#[cfg(not(target_arch = "wasm32"))]
+mod export;
 mod game;
+mod highscores;
 mod snake;
 mod food;
+mod hud;
+mod keymap;
 mod level;
+mod palette;
+mod particles;
+mod replay;
+mod scoring;
+mod stats;
+mod terminal;
+mod tileset;
+mod verify;
+mod viewport;
 
 use wasm_bindgen::prelude::*;
 
@@ -14,7 +45,7 @@ pub fn start() {
     console_error_panic_hook::set_once();
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[wasm_bindgen]
 pub enum Key {
     ArrowUp,
@@ -22,10 +53,35 @@ pub enum Key {
     ArrowLeft,
     ArrowRight,
     Space,
+    /// Quarter turn counter-clockwise from the snake's heading, for the relative key scheme.
+    TurnLeft,
+    /// Quarter turn clockwise from the snake's heading.
+    TurnRight,
     Invalid,
 }
 
 
+impl Key {
+    /// The `KeyboardEvent.code` the key is read from, the inverse of `Key::from(&str)`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Key::ArrowUp => "ArrowUp",
+            Key::ArrowDown => "ArrowDown",
+            Key::ArrowLeft => "ArrowLeft",
+            Key::ArrowRight => "ArrowRight",
+            Key::Space => "Space",
+            Key::TurnLeft => "TurnLeft",
+            Key::TurnRight => "TurnRight",
+            Key::Invalid => "Invalid",
+        }
+    }
+
+    /// Whether the key turns the snake, either to a direction or relative to its heading.
+    pub fn steers(self) -> bool {
+        Direction::from(self) != Direction::Invalid || matches!(self, Key::TurnLeft | Key::TurnRight)
+    }
+}
+
 impl From<&str> for Key {
     fn from(value: &str) -> Self {
         match value {
@@ -34,12 +90,14 @@ impl From<&str> for Key {
             "ArrowLeft" => Key::ArrowLeft,
             "ArrowRight" => Key::ArrowRight,
             "Space" => Key::Space,
+            "TurnLeft" => Key::TurnLeft,
+            "TurnRight" => Key::TurnRight,
             _ => Key::Invalid,
         }
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[wasm_bindgen]
 pub enum Direction {
     Up,
@@ -49,6 +107,36 @@ pub enum Direction {
     Invalid,
 }
 
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+    /// The direction after a quarter turn counter-clockwise.
+    pub fn turn_left(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Left,
+            Direction::Left => Direction::Down,
+            Direction::Down => Direction::Right,
+            Direction::Right => Direction::Up,
+            Direction::Invalid => Direction::Invalid,
+        }
+    }
+
+    /// The direction after a quarter turn clockwise.
+    pub fn turn_right(self) -> Direction {
+        self.turn_left().opposite()
+    }
+
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left,
+            Direction::Invalid => Direction::Invalid,
+        }
+    }
+}
+
 impl From<Key> for Direction {
     fn from(value: Key) -> Self {
         match value {
@@ -61,6 +149,7 @@ impl From<Key> for Direction {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[wasm_bindgen]
 pub struct Color {
     pub r: u8,
@@ -69,45 +158,38 @@ pub struct Color {
     pub a: u8,
 }
 
+/// Colours from the classic palette.
 impl From<GameObject> for Color {
     fn from(value: GameObject) -> Self {
-        match value {
-            GameObject::Snake => Color {
-                r: 0,
-                g: 255,
-                b: 0,
-                a: 255,
-            },
-            GameObject::Food => Color {
-                r: 255,
-                g: 0,
-                b: 0,
-                a: 255,
-            },
-            GameObject::Wall => Color { // brown wall
-                r: 139,
-                g: 69,
-                b: 19,
-                a: 255,
-            },
-            GameObject::Empty => Color {
-                r: 0,
-                g: 0,
-                b: 0,
-                a: 255,
-            },
-        }
+        Palette::classic().color(value)
     }
 }
 
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum GameObject {
-    Snake,
+    Snake(SnakePart),
+    Opponent(SnakePart),
     Food,
     Wall,
     Empty,
 }
 
+/// Which piece of a snake a cell shows. Directions point to the neighbouring segments, except for
+/// the head which points where the snake is heading. Body segments also carry a shade, counting
+/// from 0 behind the head up to `BODY_SHADES - 1` in front of the tail.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum SnakePart {
+    Head(Direction),
+    Body(Direction, Direction, u8),
+    Tail(Direction),
+}
+
+impl SnakePart {
+    /// Few shades keep the gradient smooth enough while only cells where the shade changes have
+    /// to be repainted when the snake moves.
+    pub const BODY_SHADES: u8 = 8;
+}
+
 #[derive(Clone, Copy, PartialEq)]
 #[wasm_bindgen]
 pub enum GameState {
@@ -116,10 +198,17 @@ pub enum GameState {
     GameOver,
 }
 
+/// The kinds of events, see `events::Event` for what they carry.
+#[derive(Debug, Copy, Clone, PartialEq)]
 #[wasm_bindgen]
 pub enum GameEvent {
     EatFood,
     GameOver,
     GameStart,
     GamePause,
+    OpponentDied,
+    LevelLoaded,
+    SpeedChanged,
+    AchievementUnlocked,
+    MenuRequested,
 }
\ No newline at end of file
@@ -0,0 +1,225 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use crate::{Color, GameObject, SnakePart};
+
+/// Names of the built-in palettes, the first one is the default.
+pub const PRESETS: [&str; 5] = ["classic", "high-contrast", "deuteranopia", "protanopia", "monochrome"];
+
+/// The colours game objects are drawn with.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub empty: Color,
+    pub wall: Color,
+    pub food: Color,
+    pub snake: Color,
+    pub opponent: Color,
+}
+
+impl Palette {
+    pub fn classic() -> Self {
+        Palette {
+            empty: rgb(0, 0, 0),
+            wall: rgb(139, 69, 19),
+            food: rgb(255, 0, 0),
+            snake: rgb(0, 255, 0),
+            opponent: rgb(0, 128, 255),
+        }
+    }
+
+    /// Returns one of the built-in palettes named in `PRESETS`. The colour blind palettes are
+    /// taken from the Okabe-Ito set, which keeps snakes and food apart without telling red from
+    /// green.
+    pub fn preset(name: &str) -> Option<Self> {
+        let palette = match name {
+            "classic" => Palette::classic(),
+            "high-contrast" => Palette {
+                empty: rgb(0, 0, 0),
+                wall: rgb(128, 128, 128),
+                food: rgb(255, 255, 0),
+                snake: rgb(255, 255, 255),
+                opponent: rgb(255, 0, 255),
+            },
+            "deuteranopia" => Palette {
+                empty: rgb(0, 0, 0),
+                wall: rgb(120, 120, 120),
+                food: rgb(240, 228, 66),
+                snake: rgb(0, 114, 178),
+                opponent: rgb(230, 159, 0),
+            },
+            "protanopia" => Palette {
+                empty: rgb(0, 0, 0),
+                wall: rgb(120, 120, 120),
+                food: rgb(240, 228, 66),
+                snake: rgb(86, 180, 233),
+                opponent: rgb(204, 121, 167),
+            },
+            "monochrome" => Palette {
+                empty: rgb(0, 0, 0),
+                wall: rgb(80, 80, 80),
+                food: rgb(255, 255, 255),
+                snake: rgb(200, 200, 200),
+                opponent: rgb(130, 130, 130),
+            },
+            _ => return None,
+        };
+        Some(palette)
+    }
+
+    /// Snakes fade towards the empty colour from head to tail, down to half their colour.
+    pub fn color(&self, object: GameObject) -> Color {
+        match object {
+            GameObject::Empty => self.empty,
+            GameObject::Wall => self.wall,
+            GameObject::Food => self.food,
+            GameObject::Snake(part) => self.fade(self.snake, part),
+            GameObject::Opponent(part) => self.fade(self.opponent, part),
+        }
+    }
+
+    /// The colour of the eyes on a snake's head.
+    pub fn eye_color(&self) -> Color {
+        self.empty
+    }
+
+    fn fade(&self, color: Color, part: SnakePart) -> Color {
+        let shade = match part {
+            SnakePart::Head(_) => 0,
+            SnakePart::Body(_, _, shade) => shade.min(SnakePart::BODY_SHADES - 1) as u32,
+            SnakePart::Tail(_) => SnakePart::BODY_SHADES as u32 - 1,
+        };
+        let steps = 2 * (SnakePart::BODY_SHADES as u32 - 1);
+        let mix = |from: u8, to: u8| ((from as u32 * (steps - shade) + to as u32 * shade) / steps) as u8;
+        rgb(mix(color.r, self.empty.r), mix(color.g, self.empty.g), mix(color.b, self.empty.b))
+    }
+
+    fn entries(&self) -> [(&'static str, Color); 5] {
+        [
+            ("empty", self.empty),
+            ("wall", self.wall),
+            ("food", self.food),
+            ("snake", self.snake),
+            ("opponent", self.opponent),
+        ]
+    }
+
+    fn entry_mut(&mut self, name: &str) -> Option<&mut Color> {
+        match name {
+            "empty" => Some(&mut self.empty),
+            "wall" => Some(&mut self.wall),
+            "food" => Some(&mut self.food),
+            "snake" => Some(&mut self.snake),
+            "opponent" => Some(&mut self.opponent),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette::classic()
+    }
+}
+
+fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b, a: 255 }
+}
+
+/// Writes one `name #rrggbb` line per object.
+impl Display for Palette {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (name, color) in self.entries() {
+            writeln!(f, "{name} #{:02x}{:02x}{:02x}", color.r, color.g, color.b)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads `name #rrggbb` lines, or `name: "#rrggbb",` as in JSON. Lines may start with a preset
+/// name to build on, objects that aren't named keep the colours of that preset or of the classic
+/// palette. Empty lines, braces and lines starting with `//` are ignored.
+impl FromStr for Palette {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut palette = Palette::classic();
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("//") || line == "{" || line == "}" {
+                continue;
+            }
+            let line = line.trim_end_matches(',');
+            let (name, value) = line
+                .split_once(|c: char| c == ':' || c.is_whitespace())
+                .map(|(name, value)| (name, value.trim()))
+                .unwrap_or((line, ""));
+            let name = name.trim_matches('"');
+            let value = value.trim_matches('"');
+            if value.is_empty() {
+                palette = Palette::preset(name).ok_or_else(|| format!("Unknown palette: {name}"))?;
+                continue;
+            }
+
+            let color = palette
+                .entry_mut(name)
+                .ok_or_else(|| format!("Unknown palette entry in line: {line}"))?;
+            *color = parse_color(value).ok_or_else(|| format!("Invalid colour in line: {line}"))?;
+        }
+        Ok(palette)
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    let hex = value.strip_prefix('#')?;
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Direction;
+
+    #[test]
+    fn palette_presets_exist() {
+        for name in PRESETS {
+            assert!(Palette::preset(name).is_some(), "{name}");
+        }
+        assert_eq!(Palette::preset(PRESETS[0]), Some(Palette::default()));
+        assert_eq!(Palette::preset("sepia"), None);
+    }
+
+    #[test]
+    fn palette_fades_snakes_towards_the_tail() {
+        let palette = Palette::classic();
+        let head = palette.color(GameObject::Snake(SnakePart::Head(Direction::Up)));
+        let body = palette.color(GameObject::Snake(SnakePart::Body(Direction::Up, Direction::Down, 3)));
+        let tail = palette.color(GameObject::Snake(SnakePart::Tail(Direction::Up)));
+        assert_eq!(head, palette.snake);
+        assert!(head.g > body.g && body.g > tail.g);
+        assert_eq!(tail, rgb(0, 127, 0));
+    }
+
+    #[test]
+    fn palette_round_trips_through_text() {
+        let palette = Palette::preset("deuteranopia").unwrap();
+        assert_eq!(palette.to_string().parse::<Palette>(), Ok(palette));
+    }
+
+    #[test]
+    fn palette_parses_json_like_text() {
+        let text = "{\n  \"monochrome\",\n  \"food\": \"#FF8000\",\n  // the snake stays grey\n}";
+        let palette: Palette = text.parse().unwrap();
+        assert_eq!(palette.food, rgb(255, 128, 0));
+        assert_eq!(palette.snake, Palette::preset("monochrome").unwrap().snake);
+    }
+
+    #[test]
+    fn palette_rejects_invalid_text() {
+        assert!("snake #00ff0".parse::<Palette>().is_err());
+        assert!("snake 00ff00".parse::<Palette>().is_err());
+        assert!("grass #00ff00".parse::<Palette>().is_err());
+        assert!("sepia".parse::<Palette>().is_err());
+    }
+}
@@ -0,0 +1,217 @@
+use std::f32::consts::TAU;
+use crate::board::Board;
+use crate::canvas::{Canvas, Rect};
+use crate::game::TICK_MILLIS;
+use crate::Color;
+
+const BURST_PARTICLES: usize = 16;
+const BURST_SPEED: f32 = 0.15; // cells per tick
+const BURST_TICKS: u32 = 40;
+const SCATTER_SPEED: f32 = 0.08;
+const SCATTER_TICKS: u32 = 90;
+/// Long snakes would mostly fill the screen with particles, so only some segments scatter.
+const MAX_SCATTER_PARTICLES: usize = 300;
+const SPARKLE_PARTICLES: usize = 6;
+const SPARKLE_TICKS: u32 = 60;
+const SPARKLE_COLOR: Color = Color { r: 255, g: 255, b: 200, a: 255 };
+/// Velocities shrink by this factor every tick.
+const DRAG: f32 = 0.96;
+const SHAKE_TICKS: u32 = 45;
+const SHAKE_CELLS: f32 = 0.8;
+
+struct Particle {
+    x: f32,
+    y: f32,
+    vx: f32,
+    vy: f32,
+    ticks_left: u32,
+    ticks: u32,
+    color: Color,
+}
+
+/// Short-lived effects drawn over the board. Particles move in board cells on a fixed tick of
+/// their own, so they keep moving while the game is paused or over. They use their own random
+/// numbers and never change how a game plays.
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    rng: fastrand::Rng,
+    tick_accumulator: f32,
+    shake_ticks_left: u32,
+}
+
+impl ParticleSystem {
+    pub fn new() -> Self {
+        ParticleSystem {
+            particles: Vec::new(),
+            rng: fastrand::Rng::with_seed(0),
+            tick_accumulator: 0.0,
+            shake_ticks_left: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+        self.shake_ticks_left = 0;
+    }
+
+    /// Whether there is nothing to draw, no particles and no shake.
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty() && self.shake_ticks_left == 0
+    }
+
+    /// Particles flying out of the middle of a cell in all directions, e.g. for eaten food.
+    pub fn burst(&mut self, x: usize, y: usize, color: Color) {
+        for index in 0..BURST_PARTICLES {
+            let angle = TAU * (index as f32 + self.rng.f32()) / BURST_PARTICLES as f32;
+            let speed = BURST_SPEED * (0.5 + self.rng.f32());
+            self.emit(x, y, angle, speed, BURST_TICKS, color);
+        }
+    }
+
+    /// Breaks a snake into particles drifting away from its segments.
+    pub fn scatter<'a>(&mut self, segments: impl Iterator<Item = &'a (usize, usize)>, color: Color) {
+        for &(x, y) in segments.take(MAX_SCATTER_PARTICLES) {
+            let angle = TAU * self.rng.f32();
+            let speed = SCATTER_SPEED * self.rng.f32();
+            let ticks = SCATTER_TICKS / 2 + self.rng.u32(0..SCATTER_TICKS / 2);
+            self.emit(x, y, angle, speed, ticks, color);
+        }
+    }
+
+    /// A few slow glints around a cell, e.g. to show where new food appeared.
+    pub fn sparkle(&mut self, x: usize, y: usize) {
+        for _ in 0..SPARKLE_PARTICLES {
+            let angle = TAU * self.rng.f32();
+            let ticks = SPARKLE_TICKS / 2 + self.rng.u32(0..SPARKLE_TICKS / 2);
+            self.emit(x, y, angle, BURST_SPEED / 8.0, ticks, SPARKLE_COLOR);
+        }
+    }
+
+    /// Starts shaking the screen, see `shake_offset`.
+    pub fn shake(&mut self) {
+        self.shake_ticks_left = SHAKE_TICKS;
+    }
+
+    fn emit(&mut self, x: usize, y: usize, angle: f32, speed: f32, ticks: u32, color: Color) {
+        self.particles.push(Particle {
+            x: x as f32 + 0.5,
+            y: y as f32 + 0.5,
+            vx: angle.cos() * speed,
+            vy: angle.sin() * speed,
+            ticks_left: ticks,
+            ticks,
+            color,
+        });
+    }
+
+    /// Advances the particles by the ticks that fit into the elapsed time.
+    pub fn update(&mut self, delta_time: f32) {
+        self.tick_accumulator += delta_time;
+        while self.tick_accumulator >= TICK_MILLIS {
+            self.tick_accumulator -= TICK_MILLIS;
+            self.tick();
+        }
+    }
+
+    pub fn tick(&mut self) {
+        for particle in &mut self.particles {
+            particle.x += particle.vx;
+            particle.y += particle.vy;
+            particle.vx *= DRAG;
+            particle.vy *= DRAG;
+            particle.ticks_left -= 1;
+        }
+        self.particles.retain(|particle| particle.ticks_left > 0);
+        self.shake_ticks_left = self.shake_ticks_left.saturating_sub(1);
+    }
+
+    /// How far the screen is moved by the shake right now, in cells. It wobbles and calms down
+    /// over `SHAKE_TICKS` ticks.
+    pub fn shake_offset(&self) -> (f32, f32) {
+        if self.shake_ticks_left == 0 {
+            return (0.0, 0.0);
+        }
+        let strength = SHAKE_CELLS * self.shake_ticks_left as f32 / SHAKE_TICKS as f32;
+        let phase = self.shake_ticks_left as f32;
+        (strength * (phase * 1.7).sin(), strength * (phase * 2.3).cos())
+    }
+
+    /// Draws the particles in the viewport over the rendered board, fading out as they get older. Returns the
+    /// rectangles drawn over.
+    pub fn render(&self, canvas: &mut Canvas, board: &Board) -> Vec<Rect> {
+        let (cell_width, cell_height) = board.cell_size();
+        let size = (cell_width.min(cell_height) / 2).max(1);
+        let (width, height) = (board.get_width() as f32, board.get_height() as f32);
+        let mut rects = Vec::new();
+        for particle in &self.particles {
+            if particle.x < 0.0 || particle.y < 0.0 || particle.x >= width || particle.y >= height {
+                continue;
+            }
+            let Some((cell_left, cell_top, cell_width, cell_height)) = board.cell_rect(particle.x as usize, particle.y as usize) else {
+                continue;
+            };
+            let left = cell_left + (particle.x.fract() * cell_width as f32) as usize;
+            let top = cell_top + (particle.y.fract() * cell_height as f32) as usize;
+            let alpha = (255 * particle.ticks_left / particle.ticks) as u8;
+            let rect = (left.saturating_sub(size / 2), top.saturating_sub(size / 2), size, size);
+            rects.push(canvas.blend_rect(rect, particle.color, alpha));
+        }
+        rects
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: Color = Color { r: 255, g: 0, b: 0, a: 255 };
+
+    #[test]
+    fn particles_fade_out() {
+        let mut particles = ParticleSystem::new();
+        particles.burst(5, 5, RED);
+        particles.sparkle(1, 1);
+        assert_eq!(particles.particles.len(), BURST_PARTICLES + SPARKLE_PARTICLES);
+        for _ in 0..BURST_TICKS {
+            particles.tick();
+        }
+        assert!(particles.particles.len() <= SPARKLE_PARTICLES);
+        particles.update(SPARKLE_TICKS as f32 * TICK_MILLIS);
+        assert_eq!(particles.particles.len(), 0);
+    }
+
+    #[test]
+    fn particles_scatter_a_limited_number_of_segments() {
+        let mut particles = ParticleSystem::new();
+        let segments: Vec<(usize, usize)> = (0..1000).map(|index| (index % 100, index / 100)).collect();
+        particles.scatter(segments.iter(), RED);
+        assert_eq!(particles.particles.len(), MAX_SCATTER_PARTICLES);
+    }
+
+    #[test]
+    fn particles_shake_calms_down() {
+        let mut particles = ParticleSystem::new();
+        assert_eq!(particles.shake_offset(), (0.0, 0.0));
+        particles.shake();
+        let (dx, dy) = particles.shake_offset();
+        assert!(dx != 0.0 || dy != 0.0);
+        for _ in 0..SHAKE_TICKS {
+            particles.tick();
+        }
+        assert_eq!(particles.shake_offset(), (0.0, 0.0));
+    }
+
+    #[test]
+    fn particles_are_drawn_inside_the_screen() {
+        let board = Board::new(4, 4, 4, 4);
+        let mut buffer = vec![0; 16 * 16 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 16, 16);
+        let mut particles = ParticleSystem::new();
+        particles.burst(0, 0, RED);
+        particles.burst(2, 2, RED);
+        let rects = particles.render(&mut canvas, &board);
+        assert_eq!(rects.len(), 2 * BURST_PARTICLES);
+        assert!(rects.iter().all(|&(left, top, width, height)| left + width <= 16 && top + height <= 16));
+        assert_eq!(&buffer[(10 * 16 + 10) * 4..(10 * 16 + 10) * 4 + 3], &[255, 0, 0]);
+    }
+}
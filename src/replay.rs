@@ -0,0 +1,172 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use crate::controller::ControllerKind;
+use crate::Key;
+
+const HEADER: &str = "snake-replay 1";
+
+/// Everything needed to play a game again: the level, the random seed, who steered the player's
+/// snake, the computer snakes and the keys the player pressed, each stored with the tick it was
+/// pressed at.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replay {
+    pub level: String,
+    pub seed: u64,
+    pub player: ControllerKind,
+    pub opponents: Vec<ControllerKind>,
+    pub inputs: Vec<(u64, Key)>,
+    pub ticks: u64,
+}
+
+impl Replay {
+    pub fn new(level: &str, seed: u64, opponents: Vec<ControllerKind>) -> Self {
+        Replay {
+            level: level.to_string(),
+            seed,
+            player: ControllerKind::Keyboard,
+            opponents,
+            inputs: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    /// A replay of a game where the player's snake is steered by `player`, e.g. a bot.
+    pub fn with_player(mut self, player: ControllerKind) -> Self {
+        self.player = player;
+        self
+    }
+
+    /// The high-score table the game counts for, see `mode_name`.
+    pub fn mode(&self) -> String {
+        mode_name(self.player, &self.opponents)
+    }
+
+    /// Remembers a key pressed after `tick` ticks have been simulated.
+    pub fn record(&mut self, tick: u64, key: Key) {
+        self.inputs.push((tick, key));
+    }
+}
+
+/// Writes the replay as plain text, one `name value` pair per line.
+impl Display for Replay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "level {}", self.level)?;
+        writeln!(f, "seed {}", self.seed)?;
+        writeln!(f, "player {}", self.player.name())?;
+        let opponents: Vec<&str> = self.opponents.iter().map(|kind| kind.name()).collect();
+        writeln!(f, "opponents {}", opponents.join(" "))?;
+        writeln!(f, "ticks {}", self.ticks)?;
+        for (tick, key) in &self.inputs {
+            writeln!(f, "input {tick} {}", key.name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Replays without a `player` line were recorded with the keyboard.
+impl FromStr for Replay {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err("Not a replay, missing header".to_string());
+        }
+
+        let mut replay = Replay::new("", 0, Vec::new());
+        for line in lines {
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            let number = |value: &str| {
+                value.parse::<u64>().map_err(|_| format!("Invalid number in line: {line}"))
+            };
+            match name {
+                "level" => replay.level = value.to_string(),
+                "seed" => replay.seed = number(value)?,
+                "ticks" => replay.ticks = number(value)?,
+                "player" => replay.player = value.parse()?,
+                "opponents" => {
+                    replay.opponents = value
+                        .split_whitespace()
+                        .map(ControllerKind::from_str)
+                        .collect::<Result<_, _>>()?;
+                }
+                "input" => {
+                    let (tick, key) = value
+                        .split_once(' ')
+                        .ok_or_else(|| format!("Invalid input line: {line}"))?;
+                    let key = Key::from(key);
+                    if key == Key::Invalid {
+                        return Err(format!("Invalid key in line: {line}"));
+                    }
+                    replay.record(number(tick)?, key);
+                }
+                _ => return Err(format!("Unknown replay line: {line}")),
+            }
+        }
+
+        if replay.level.is_empty() {
+            return Err("Replay doesn't name a level".to_string());
+        }
+        Ok(replay)
+    }
+}
+
+/// The name of a high-score table: "classic", or the player's bot and the computer snakes, e.g.
+/// "autopilot" or "greedy vs-greedy-survival".
+pub(crate) fn mode_name(player: ControllerKind, opponents: &[ControllerKind]) -> String {
+    let mut parts = Vec::new();
+    if player != ControllerKind::Keyboard {
+        parts.push(player.name().to_string());
+    }
+    if !opponents.is_empty() {
+        let opponents: Vec<&str> = opponents.iter().map(|kind| kind.name()).collect();
+        parts.push(format!("vs-{}", opponents.join("-")));
+    }
+    if parts.is_empty() { "classic".to_string() } else { parts.join(" ") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replay_round_trips_through_text() {
+        let mut replay = Replay::new("Board 2", 1234, vec![ControllerKind::Greedy, ControllerKind::Survival])
+            .with_player(ControllerKind::Autopilot);
+        replay.record(10, Key::ArrowUp);
+        replay.record(42, Key::ArrowLeft);
+        replay.ticks = 100;
+
+        let text = replay.to_string();
+        assert_eq!(text.parse::<Replay>(), Ok(replay));
+    }
+
+    #[test]
+    fn replay_parses_text_without_opponents() {
+        let text = "snake-replay 1\nlevel Board 1\nseed 7\nopponents \nticks 5\ninput 3 ArrowDown\n";
+        let replay: Replay = text.parse().unwrap();
+        assert_eq!(replay.level, "Board 1");
+        assert_eq!(replay.seed, 7);
+        assert_eq!(replay.player, ControllerKind::Keyboard);
+        assert!(replay.opponents.is_empty());
+        assert_eq!(replay.inputs, vec![(3, Key::ArrowDown)]);
+    }
+
+    #[test]
+    fn replay_names_its_mode() {
+        assert_eq!(Replay::new("Board 1", 1, Vec::new()).mode(), "classic");
+        let replay = Replay::new("Board 1", 1, vec![ControllerKind::Greedy]).with_player(ControllerKind::Survival);
+        assert_eq!(replay.mode(), "survival vs-greedy");
+    }
+
+    #[test]
+    fn replay_rejects_invalid_text() {
+        assert!("level Board 1".parse::<Replay>().is_err());
+        assert!("snake-replay 1\nseed 7".parse::<Replay>().is_err());
+        assert!("snake-replay 1\nlevel Board 1\nseed seven".parse::<Replay>().is_err());
+        assert!("snake-replay 1\nlevel Board 1\ninput 3 KeyQ".parse::<Replay>().is_err());
+        assert!("snake-replay 1\nlevel Board 1\nopponents clever".parse::<Replay>().is_err());
+        assert!("snake-replay 1\nlevel Board 1\nplayer clever".parse::<Replay>().is_err());
+    }
+}
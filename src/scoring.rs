@@ -0,0 +1,106 @@
+use crate::game::TICK_MILLIS;
+use crate::snake::INITIAL_SPEED;
+
+/// Points for a piece of food at the initial speed, before any bonus.
+pub(crate) const FOOD_POINTS: u32 = 10;
+/// Every this many segments of the snake add a point to each piece of food.
+const SEGMENTS_PER_POINT: usize = 10;
+/// Food eaten within this time of the previous piece continues the combo.
+const COMBO_MILLIS: f32 = 3000.0;
+const MAX_MULTIPLIER: u32 = 8;
+
+/// Works out the points for eaten food. Food is worth more the faster and longer the snake is,
+/// and a combo multiplier grows with every piece eaten soon after the previous one.
+pub struct Scoring {
+    multiplier: u32,
+    combo_ticks_left: u32,
+}
+
+impl Scoring {
+    pub fn new() -> Self {
+        Scoring { multiplier: 1, combo_ticks_left: 0 }
+    }
+
+    /// Counts down the combo timer by one game tick. The multiplier drops back to 1 when it
+    /// runs out.
+    pub fn tick(&mut self) {
+        self.combo_ticks_left = self.combo_ticks_left.saturating_sub(1);
+        if self.combo_ticks_left == 0 {
+            self.multiplier = 1;
+        }
+    }
+
+    /// Returns the points for a piece of food eaten by a snake of the given speed and length,
+    /// and starts or continues the combo.
+    pub fn eat_food(&mut self, speed: f32, length: usize) -> u32 {
+        if self.combo_ticks_left > 0 {
+            self.multiplier = (self.multiplier + 1).min(MAX_MULTIPLIER);
+        }
+        self.combo_ticks_left = Self::combo_ticks();
+
+        let speed_points = FOOD_POINTS as f32 * speed / INITIAL_SPEED;
+        let length_points = (length / SEGMENTS_PER_POINT) as f32;
+        (speed_points + length_points).round() as u32 * self.multiplier
+    }
+
+    /// What the next piece of food is multiplied by, if it's eaten before the combo runs out.
+    pub fn get_multiplier(&self) -> u32 {
+        if self.combo_ticks_left > 0 { (self.multiplier + 1).min(MAX_MULTIPLIER) } else { 1 }
+    }
+
+    /// How much of the combo time is left, from 1 right after eating to 0 when it ran out.
+    pub fn get_combo_left(&self) -> f32 {
+        self.combo_ticks_left as f32 / Self::combo_ticks() as f32
+    }
+
+    /// Milliseconds until the combo runs out.
+    pub fn get_combo_millis_left(&self) -> f32 {
+        self.combo_ticks_left as f32 * TICK_MILLIS
+    }
+
+    fn combo_ticks() -> u32 {
+        (COMBO_MILLIS / TICK_MILLIS) as u32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scoring_rewards_speed_and_length() {
+        assert_eq!(Scoring::new().eat_food(INITIAL_SPEED, 1), FOOD_POINTS);
+        assert_eq!(Scoring::new().eat_food(2.0 * INITIAL_SPEED, 1), 2 * FOOD_POINTS);
+        assert_eq!(Scoring::new().eat_food(INITIAL_SPEED, 35), FOOD_POINTS + 3);
+    }
+
+    #[test]
+    fn scoring_combo_grows_and_runs_out() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.get_multiplier(), 1);
+        assert_eq!(scoring.eat_food(INITIAL_SPEED, 1), FOOD_POINTS);
+        assert_eq!(scoring.get_multiplier(), 2);
+        assert_eq!(scoring.get_combo_left(), 1.0);
+
+        for _ in 0..100 {
+            scoring.tick();
+        }
+        assert_eq!(scoring.eat_food(INITIAL_SPEED, 1), 2 * FOOD_POINTS);
+        assert_eq!(scoring.eat_food(INITIAL_SPEED, 1), 3 * FOOD_POINTS);
+
+        for _ in 0..Scoring::combo_ticks() {
+            scoring.tick();
+        }
+        assert_eq!(scoring.get_multiplier(), 1);
+        assert_eq!(scoring.get_combo_millis_left(), 0.0);
+        assert_eq!(scoring.eat_food(INITIAL_SPEED, 1), FOOD_POINTS);
+    }
+
+    #[test]
+    fn scoring_multiplier_is_capped() {
+        let mut scoring = Scoring::new();
+        let points: Vec<u32> = (0..12).map(|_| scoring.eat_food(INITIAL_SPEED, 1)).collect();
+        assert_eq!(points[MAX_MULTIPLIER as usize - 1], MAX_MULTIPLIER * FOOD_POINTS);
+        assert_eq!(points[11], MAX_MULTIPLIER * FOOD_POINTS);
+    }
+}
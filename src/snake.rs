@@ -1,9 +1,51 @@
 use std::collections::VecDeque;
+use std::fmt::{self, Display, Formatter};
 use crate::board::Board;
-use crate::{Direction, GameObject, Key};
+use crate::{Direction, GameObject, SnakePart};
+
+pub(crate) const INITIAL_SPEED: f32 = 5.0; // cells per second
+pub(crate) const MAX_SPEED: f32 = 40.0; // cells per second
+
+/// Why a snake died. There is no cause for leaving the board or eating poison: a snake leaving
+/// one side of the board comes back on the other, and no food is poisonous.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeathCause {
+    /// The head ran into the wall at `(x, y)`.
+    HitWall { x: usize, y: usize },
+    /// The head ran into the snake's own body, `segment` cells behind the head.
+    HitOwnBody { segment: usize },
+    /// The head ran into another snake at `(x, y)`.
+    HitOtherSnake { x: usize, y: usize },
+}
 
-const INITIAL_SPEED: f32 = 5.0; // cells per second
-const MAX_SPEED: f32 = 40.0; // cells per second
+impl DeathCause {
+    /// A short identifier, e.g. for analytics.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::HitWall { .. } => "wall",
+            DeathCause::HitOwnBody { .. } => "own-body",
+            DeathCause::HitOtherSnake { .. } => "other-snake",
+        }
+    }
+}
+
+/// Describes the cause for players, e.g. in the game over banner.
+impl Display for DeathCause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DeathCause::HitWall { .. } => write!(f, "Hit a wall"),
+            DeathCause::HitOwnBody { .. } => write!(f, "Bit its own tail"),
+            DeathCause::HitOtherSnake { .. } => write!(f, "Ran into another snake"),
+        }
+    }
+}
+
+/// What happened when a snake tried to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MoveOutcome {
+    Moved,
+    Died(DeathCause),
+}
 
 pub struct Snake {
     body: VecDeque<(usize, usize)>,
@@ -11,6 +53,8 @@ pub struct Snake {
     movement_accumulator: f32,
     speed: f32,
     grow_pending: usize,
+    moves: usize,
+    object: fn(SnakePart) -> GameObject,
 }
 
 impl Snake {
@@ -21,112 +65,158 @@ impl Snake {
             movement_accumulator: 0.0,
             speed: INITIAL_SPEED,
             grow_pending: 0,
+            moves: 0,
+            object: GameObject::Snake,
         }
     }
 
+    /// Sets the game object the snake is drawn as, e.g. to tell computer snakes apart from the player.
+    pub fn with_object(mut self, object: fn(SnakePart) -> GameObject) -> Snake {
+        self.object = object;
+        self
+    }
+
     pub fn increase_speed(&mut self, increment: f32) {
         self.speed += increment;
         if self.speed > MAX_SPEED {
             self.speed = MAX_SPEED;
-            return;
         }
     }
 
-    pub fn change_direction(&mut self, key: Key) {
-        let new_direction: Direction = key.into();
+    pub fn set_direction(&mut self, new_direction: Direction) {
         // Prevent the snake from reversing
-        if new_direction == Direction::Invalid
-            || (self.direction == Direction::Up && new_direction == Direction::Down)
-            || (self.direction == Direction::Down && new_direction == Direction::Up)
-            || (self.direction == Direction::Left && new_direction == Direction::Right)
-            || (self.direction == Direction::Right && new_direction == Direction::Left)
-        {
+        if new_direction == Direction::Invalid || new_direction == self.direction.opposite() {
             return;
         }
         self.direction = new_direction;
     }
 
-    pub fn move_forward(&mut self, board: &Board, delta_miliseconds: f32) -> bool {
+    pub fn get_direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// Moves the snake as far as it gets in the elapsed time, stopping early if it dies.
+    pub fn move_forward(&mut self, board: &Board, delta_miliseconds: f32) -> MoveOutcome {
+        let distance = self.advance(delta_miliseconds);
+        for _ in 0..distance {
+            if let MoveOutcome::Died(cause) = self.step(board) {
+                return MoveOutcome::Died(cause);
+            }
+        }
+        MoveOutcome::Moved
+    }
+
+    /// Accumulates the elapsed time and returns how many cells the snake has to move now.
+    pub fn advance(&mut self, delta_miliseconds: f32) -> usize {
         let delta_secconds = delta_miliseconds / 1000.0;
         self.movement_accumulator += self.speed * delta_secconds;
         let distance = self.movement_accumulator.floor() as usize;
-        if distance == 0 {
-            return true;
-        }
         self.movement_accumulator -= distance as f32;
+        distance
+    }
 
-        for _ in 0..distance {
-            let (head_x, head_y) = self.body[0];
-            let (new_head_x, new_head_y) = self.new_head_position(board, head_x, head_y);
-
-            if board.is_wall_at(new_head_x, new_head_y) {
-                return false;
-            }
-
-            if self.is_snake_at(new_head_x, new_head_y) {
-                return false;
-            }
+    /// Moves the snake a single cell in its current direction, unless it crashes.
+    pub fn step(&mut self, board: &Board) -> MoveOutcome {
+        let (head_x, head_y) = self.body[0];
+        let (new_head_x, new_head_y) = board.neighbour(head_x, head_y, self.direction);
 
-            self.body.push_front((new_head_x, new_head_y));
-            if self.grow_pending > 0 {
-                self.grow_pending -= 1;
-                continue;
-            }
-            self.body.pop_back();
+        if board.is_wall_at(new_head_x, new_head_y) {
+            return MoveOutcome::Died(DeathCause::HitWall { x: new_head_x, y: new_head_y });
         }
 
-        true
-    }
+        // The tail moves out of the way unless the snake is growing
+        let blocking = if self.grow_pending > 0 { self.body.len() } else { self.body.len() - 1 };
+        if let Some(segment) = self.body.iter().take(blocking).position(|&cell| cell == (new_head_x, new_head_y)) {
+            return MoveOutcome::Died(DeathCause::HitOwnBody { segment });
+        }
 
-    fn new_head_position(&mut self, board: &Board, head_x: usize, head_y: usize) -> (usize, usize) {
-        match self.direction {
-            Direction::Up => {
-                if head_y == 0 {
-                    (head_x, board.get_height() - 1)
-                } else {
-                    (head_x, head_y - 1)
-                }
-            }
-            Direction::Down => {
-                if head_y == board.get_height() - 1 {
-                    (head_x, 0)
-                } else {
-                    (head_x, head_y + 1)
-                }
-            }
-            Direction::Left => {
-                if head_x == 0 {
-                    (board.get_width() - 1, head_y)
-                } else {
-                    (head_x - 1, head_y)
-                }
-            }
-            Direction::Right => {
-                if head_x == board.get_width() - 1 {
-                    (0, head_y)
-                } else {
-                    (head_x + 1, head_y)
-                }
-            }
-            _ => (head_x, head_y), // Should not happen, but makes the compiler happy
+        self.body.push_front((new_head_x, new_head_y));
+        self.moves += 1;
+        if self.grow_pending > 0 {
+            self.grow_pending -= 1;
+        } else {
+            self.body.pop_back();
         }
+        MoveOutcome::Moved
     }
 
     pub fn grow(&mut self, num_blocks: usize) {
         self.grow_pending += num_blocks;
     }
 
+    /// Drops the growth still to come, e.g. when there is no room left to grow into.
+    pub fn stop_growing(&mut self) {
+        self.grow_pending = 0;
+    }
+
     pub fn get_head_pos(&self) -> (usize, usize) {
         self.body[0]
     }
 
+    pub fn get_tail_pos(&self) -> (usize, usize) {
+        self.body[self.body.len() - 1]
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    /// How many cells the snake has moved since it was created.
+    pub fn get_moves(&self) -> usize {
+        self.moves
+    }
+
+    pub fn get_speed(&self) -> f32 {
+        self.speed
+    }
+
+    pub fn get_grow_pending(&self) -> usize {
+        self.grow_pending
+    }
+
+    /// Iterates the body cells from head to tail.
+    pub fn body(&self) -> impl DoubleEndedIterator<Item = &(usize, usize)> {
+        self.body.iter()
+    }
+
     pub fn is_snake_at(&self, x: usize, y: usize) -> bool {
         self.body.iter().any(|&(sx, sy)| sx == x && sy == y)
     }
 
     pub fn render_to_board(&self, board: &mut Board) {
-        for &(x, y) in &self.body {
-            board.set_cell(x, y, GameObject::Snake);
+        for (idx, &(x, y)) in self.body.iter().enumerate() {
+            let towards = |other: Option<&(usize, usize)>| {
+                other.and_then(|&other| board.direction_between((x, y), other))
+            };
+            let previous = idx.checked_sub(1).and_then(|previous| self.body.get(previous));
+            let part = match (towards(previous), towards(self.body.get(idx + 1))) {
+                (Some(previous), Some(next)) => {
+                    let shade = idx * SnakePart::BODY_SHADES as usize / self.body.len();
+                    SnakePart::Body(previous, next, shade as u8)
+                }
+                (Some(previous), None) => SnakePart::Tail(previous),
+                _ => SnakePart::Head(self.direction),
+            };
+            board.set_cell(x, y, (self.object)(part));
+        }
+    }
+
+    /// Shows how far the snake has got towards its next cell: the head reaches into the cell
+    /// ahead and, unless the snake is growing, the tail leaves its cell.
+    pub fn render_motion_to_board(&self, board: &mut Board) {
+        let progress = self.movement_accumulator;
+        let (head_x, head_y) = self.get_head_pos();
+        let (next_x, next_y) = board.neighbour(head_x, head_y, self.direction);
+        if matches!(board.get_cell(next_x, next_y), Some(GameObject::Empty | GameObject::Food)) {
+            let head = (self.object)(SnakePart::Head(self.direction));
+            board.add_motion(next_x, next_y, self.direction.opposite(), progress, head);
+        }
+
+        if self.grow_pending == 0 && self.body.len() > 1 {
+            let tail = self.get_tail_pos();
+            if let Some(direction) = board.direction_between(tail, self.body[self.body.len() - 2]) {
+                board.add_motion(tail.0, tail.1, direction.opposite(), progress, GameObject::Empty);
+            }
         }
     }
 }
@@ -152,24 +242,24 @@ mod tests {
     #[test]
     fn snake_changes_direction_correctly() {
         let mut snake = Snake::new(5, 5);
-        snake.change_direction(Key::ArrowUp);
+        snake.set_direction(Direction::Up);
         assert_eq!(snake.direction, Direction::Up);
-        snake.change_direction(Key::ArrowDown);
+        snake.set_direction(Direction::Down);
         assert_eq!(snake.direction, Direction::Up);
 
-        snake.change_direction(Key::ArrowLeft);
+        snake.set_direction(Direction::Left);
         assert_eq!(snake.direction, Direction::Left);
-        snake.change_direction(Key::ArrowRight);
+        snake.set_direction(Direction::Right);
         assert_eq!(snake.direction, Direction::Left);
 
-        snake.change_direction(Key::ArrowDown);
+        snake.set_direction(Direction::Down);
         assert_eq!(snake.direction, Direction::Down);
-        snake.change_direction(Key::ArrowUp);
+        snake.set_direction(Direction::Up);
         assert_eq!(snake.direction, Direction::Down);
 
-        snake.change_direction(Key::ArrowRight);
+        snake.set_direction(Direction::Right);
         assert_eq!(snake.direction, Direction::Right);
-        snake.change_direction(Key::ArrowLeft);
+        snake.set_direction(Direction::Left);
         assert_eq!(snake.direction, Direction::Right);
     }
 
@@ -181,15 +271,15 @@ mod tests {
         snake.move_forward(&board, 200.0); // 200
         assert_eq!(snake.body[0], (6, 5)); // wraps around to (1, 5)
 
-        snake.change_direction(Key::ArrowDown);
+        snake.set_direction(Direction::Down);
         snake.move_forward(&board, 200.0); // 200 ms
         assert_eq!(snake.body[0], (6, 6));
 
-        snake.change_direction(Key::ArrowLeft);
+        snake.set_direction(Direction::Left);
         snake.move_forward(&board, 400.0); // 400 ms
         assert_eq!(snake.body[0], (4, 6));
 
-        snake.change_direction(Key::ArrowUp);
+        snake.set_direction(Direction::Up);
         snake.move_forward(&board, 1000.0); // 1 second
         assert_eq!(snake.body[0], (4, 1));
     }
@@ -202,15 +292,15 @@ mod tests {
         snake.move_forward(&board, 200.0);
         assert_eq!(snake.body[0], (0, 0));
 
-        snake.change_direction(Key::ArrowUp);
+        snake.set_direction(Direction::Up);
         snake.move_forward(&board, 200.0);
         assert_eq!(snake.body[0], (0, 9));
 
-        snake.change_direction(Key::ArrowLeft);
+        snake.set_direction(Direction::Left);
         snake.move_forward(&board, 200.0);
         assert_eq!(snake.body[0], (9, 9));
 
-        snake.change_direction(Key::ArrowDown);
+        snake.set_direction(Direction::Down);
         snake.move_forward(&board, 200.0);
         assert_eq!(snake.body[0], (9, 0));
     }
@@ -236,9 +326,9 @@ mod tests {
 
         let mut snake = Snake::new(1, 1);
         snake.speed = 5.0; // 5 blocks per second
-        assert!(snake.move_forward(&board, 200.0));
-        snake.change_direction(Key::ArrowDown);
-        assert!(!snake.move_forward(&board, 200.0));
+        assert_eq!(snake.move_forward(&board, 200.0), MoveOutcome::Moved);
+        snake.set_direction(Direction::Down);
+        assert_eq!(snake.move_forward(&board, 200.0), MoveOutcome::Died(DeathCause::HitWall { x: 2, y: 2 }));
     }
 
     #[test]
@@ -251,14 +341,33 @@ mod tests {
         // Move right
         snake.move_forward(&board, 1000.0);
         // Move down
-        snake.change_direction(Key::ArrowDown);
+        snake.set_direction(Direction::Down);
         snake.move_forward(&board, 1000.0);
         // Move left
-        snake.change_direction(Key::ArrowLeft);
+        snake.set_direction(Direction::Left);
         snake.move_forward(&board, 1000.0);
         // Move up - this should cause a collision with itself
-        snake.change_direction(Key::ArrowUp);
-        assert!(!snake.move_forward(&board, 1000.0));
+        snake.set_direction(Direction::Up);
+        assert_eq!(snake.move_forward(&board, 1000.0), MoveOutcome::Died(DeathCause::HitOwnBody { segment: 3 }));
+    }
+
+    #[test]
+    fn snake_follows_its_own_tail() {
+        let board = Board::new(10, 10, 1, 1);
+        let mut snake = Snake::new(5, 5);
+        snake.grow(3);
+        for direction in [Direction::Right, Direction::Down, Direction::Left] {
+            snake.set_direction(direction);
+            assert_eq!(snake.step(&board), MoveOutcome::Moved);
+        }
+        // The tail leaves the cell the head moves into
+        snake.set_direction(Direction::Up);
+        for _ in 0..8 {
+            assert_eq!(snake.step(&board), MoveOutcome::Moved);
+            let direction = snake.get_direction().turn_right();
+            snake.set_direction(direction);
+        }
+        assert_eq!(snake.len(), 4);
     }
 
     #[test]
@@ -269,9 +378,52 @@ mod tests {
         let mut board = Board::new(10, 10, 1, 1);
         snake.move_forward(&board, 1000.0);
         snake.render_to_board(&mut board);
-        assert_eq!(board.get_cell(4, 2), Some(GameObject::Snake));
-        assert_eq!(board.get_cell(3, 2), Some(GameObject::Snake));
-        assert_eq!(board.get_cell(2, 2), Some(GameObject::Snake));
+        assert_eq!(board.get_cell(4, 2), Some(GameObject::Snake(SnakePart::Head(Direction::Right))));
+        assert_eq!(
+            board.get_cell(3, 2),
+            Some(GameObject::Snake(SnakePart::Body(Direction::Right, Direction::Left, 2)))
+        );
+        assert_eq!(board.get_cell(2, 2), Some(GameObject::Snake(SnakePart::Tail(Direction::Right))));
+    }
+
+    #[test]
+    fn snake_renders_corners_and_wrapped_segments() {
+        let board = Board::new(5, 5, 1, 1);
+        let mut snake = Snake::new(3, 0).with_object(GameObject::Opponent);
+        snake.grow(2);
+        snake.step(&board);
+        snake.set_direction(Direction::Up);
+        snake.step(&board);
+
+        let mut board = Board::new(5, 5, 1, 1);
+        snake.render_to_board(&mut board);
+        assert_eq!(board.get_cell(4, 4), Some(GameObject::Opponent(SnakePart::Head(Direction::Up))));
+        assert_eq!(
+            board.get_cell(4, 0),
+            Some(GameObject::Opponent(SnakePart::Body(Direction::Up, Direction::Left, 2)))
+        );
+        assert_eq!(board.get_cell(3, 0), Some(GameObject::Opponent(SnakePart::Tail(Direction::Right))));
+    }
+
+    #[test]
+    fn snake_renders_its_motion_between_cells() {
+        let mut board = Board::new(10, 1, 10, 10);
+        board.set_smooth_movement(true);
+        let mut snake = Snake::new(2, 0);
+        snake.grow(1);
+        snake.step(&board);
+        snake.advance(100.0);
+
+        snake.render_to_board(&mut board);
+        snake.render_motion_to_board(&mut board);
+        let mut buffer = vec![0; 100 * 10 * 4];
+        board.render_to_buffer(&mut buffer);
+
+        // A tenth of a second at 5 cells per second: the head is halfway into (4, 0), the tail halfway
+        // out of (2, 0)
+        let green = |x: usize| buffer[x * 4 + 1];
+        assert_eq!([green(40), green(44), green(45), green(49)], [255, 255, 0, 0]);
+        assert_eq!([green(20), green(24), green(25), green(29)], [0, 0, 127, 127]);
     }
 
     #[test]
@@ -0,0 +1,170 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+use wasm_bindgen::prelude::wasm_bindgen;
+
+const HEADER: &str = "snake-stats 1";
+
+/// What the player's snake did in the current game.
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[wasm_bindgen]
+pub struct RunStats {
+    pub food_eaten: u32,
+    pub max_length: usize,
+    /// The highest speed reached, in cells per second.
+    pub max_speed: f32,
+    pub time_alive_millis: f64,
+    /// Cells moved.
+    pub distance: u32,
+    pub turns: u32,
+    /// Quarter turns counter-clockwise, included in `turns`.
+    pub left_turns: u32,
+    /// Turns away from a wall or snake right in front of the head.
+    pub near_misses: u32,
+    /// Time the game was paused after it had started.
+    pub paused_millis: f64,
+}
+
+impl RunStats {
+    pub fn new(length: usize, speed: f32) -> Self {
+        RunStats {
+            food_eaten: 0,
+            max_length: length,
+            max_speed: speed,
+            time_alive_millis: 0.0,
+            distance: 0,
+            turns: 0,
+            left_turns: 0,
+            near_misses: 0,
+            paused_millis: 0.0,
+        }
+    }
+
+    /// Remembers the length and speed if they are the highest so far.
+    pub fn update_records(&mut self, length: usize, speed: f32) {
+        self.max_length = self.max_length.max(length);
+        self.max_speed = self.max_speed.max(speed);
+    }
+}
+
+/// Totals and records of all finished games the player steered.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[wasm_bindgen]
+pub struct LifetimeStats {
+    pub games_played: u32,
+    pub food_eaten: u32,
+    pub max_length: usize,
+    pub max_speed: f32,
+    pub time_alive_millis: f64,
+    pub distance: u32,
+    pub turns: u32,
+    pub near_misses: u32,
+    pub paused_millis: f64,
+}
+
+impl LifetimeStats {
+    pub fn new() -> Self {
+        LifetimeStats::default()
+    }
+
+    pub fn add_run(&mut self, run: &RunStats) {
+        self.games_played += 1;
+        self.food_eaten += run.food_eaten;
+        self.max_length = self.max_length.max(run.max_length);
+        self.max_speed = self.max_speed.max(run.max_speed);
+        self.time_alive_millis += run.time_alive_millis;
+        self.distance += run.distance;
+        self.turns += run.turns;
+        self.near_misses += run.near_misses;
+        self.paused_millis += run.paused_millis;
+    }
+}
+
+/// Writes the stats as plain text, one `name value` pair per line.
+impl Display for LifetimeStats {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{HEADER}")?;
+        writeln!(f, "games {}", self.games_played)?;
+        writeln!(f, "food {}", self.food_eaten)?;
+        writeln!(f, "max-length {}", self.max_length)?;
+        writeln!(f, "max-speed {}", self.max_speed)?;
+        writeln!(f, "time-alive {}", self.time_alive_millis)?;
+        writeln!(f, "distance {}", self.distance)?;
+        writeln!(f, "turns {}", self.turns)?;
+        writeln!(f, "near-misses {}", self.near_misses)?;
+        writeln!(f, "paused {}", self.paused_millis)
+    }
+}
+
+/// Missing values are 0, so stats written by older versions still load.
+impl FromStr for LifetimeStats {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let mut lines = text.lines().map(str::trim).filter(|line| !line.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err("Not a stats file, missing header".to_string());
+        }
+
+        let mut stats = LifetimeStats::new();
+        for line in lines {
+            let (name, value) = line.split_once(' ').unwrap_or((line, ""));
+            let invalid = || format!("Invalid number in line: {line}");
+            match name {
+                "games" => stats.games_played = value.parse().map_err(|_| invalid())?,
+                "food" => stats.food_eaten = value.parse().map_err(|_| invalid())?,
+                "max-length" => stats.max_length = value.parse().map_err(|_| invalid())?,
+                "max-speed" => stats.max_speed = value.parse().map_err(|_| invalid())?,
+                "time-alive" => stats.time_alive_millis = value.parse().map_err(|_| invalid())?,
+                "distance" => stats.distance = value.parse().map_err(|_| invalid())?,
+                "turns" => stats.turns = value.parse().map_err(|_| invalid())?,
+                "near-misses" => stats.near_misses = value.parse().map_err(|_| invalid())?,
+                "paused" => stats.paused_millis = value.parse().map_err(|_| invalid())?,
+                _ => return Err(format!("Unknown stats line: {line}")),
+            }
+        }
+        Ok(stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_add_up_runs() {
+        let mut first = RunStats::new(9, 10.0);
+        first.food_eaten = 3;
+        first.distance = 200;
+        first.update_records(15, 10.15);
+        first.update_records(12, 10.0);
+        assert_eq!((first.max_length, first.max_speed), (15, 10.15));
+        let mut second = RunStats::new(9, 10.0);
+        second.food_eaten = 1;
+        second.time_alive_millis = 500.0;
+
+        let mut lifetime = LifetimeStats::new();
+        lifetime.add_run(&first);
+        lifetime.add_run(&second);
+        assert_eq!(lifetime.games_played, 2);
+        assert_eq!(lifetime.food_eaten, 4);
+        assert_eq!(lifetime.distance, 200);
+        assert_eq!(lifetime.max_length, 15);
+        assert_eq!(lifetime.time_alive_millis, 500.0);
+    }
+
+    #[test]
+    fn stats_round_trip_through_text() {
+        let mut run = RunStats::new(20, 11.5);
+        run.turns = 7;
+        run.near_misses = 2;
+        run.paused_millis = 1234.5;
+        let mut lifetime = LifetimeStats::new();
+        lifetime.add_run(&run);
+
+        assert_eq!(lifetime.to_string().parse::<LifetimeStats>(), Ok(lifetime));
+        assert_eq!("snake-stats 1\ngames 3".parse::<LifetimeStats>().unwrap().games_played, 3);
+        assert!("games 3".parse::<LifetimeStats>().is_err());
+        assert!("snake-stats 1\ngames many".parse::<LifetimeStats>().is_err());
+        assert!("snake-stats 1\nlives 3".parse::<LifetimeStats>().is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt::Write;
+use crate::board::Board;
+use crate::{Color, GameObject};
+
+const UPPER_HALF_BLOCK: char = '▀';
+const RESET: &str = "\x1b[0m";
+
+/// Draws the board with ANSI true colour escape codes. Every character shows two cells on top of
+/// each other, the upper one in the foreground colour and the lower one in the background colour,
+/// which keeps cells roughly square in a terminal. Lines end with `\r\n` so the output also works
+/// in raw mode.
+pub fn render_ansi(board: &Board) -> String {
+    let mut output = String::new();
+    for y in (0..board.get_height()).step_by(2) {
+        let mut current = None;
+        for x in 0..board.get_width() {
+            let upper = cell_color(board, x, y);
+            let lower = board.get_cell(x, y + 1).map(|object| board.get_palette().color(object));
+            let colors = (upper, lower);
+            if current != Some(colors) {
+                let _ = write!(output, "\x1b[38;2;{};{};{}m", upper.r, upper.g, upper.b);
+                match &lower {
+                    Some(lower) => {
+                        let _ = write!(output, "\x1b[48;2;{};{};{}m", lower.r, lower.g, lower.b);
+                    }
+                    None => output.push_str("\x1b[49m"),
+                }
+                current = Some(colors);
+            }
+            output.push(UPPER_HALF_BLOCK);
+        }
+        output.push_str(RESET);
+        output.push_str("\r\n");
+    }
+    output
+}
+
+fn cell_color(board: &Board, x: usize, y: usize) -> Color {
+    board.get_palette().color(board.get_cell(x, y).unwrap_or(GameObject::Empty))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Direction, SnakePart};
+
+    #[test]
+    fn ansi_renderer_draws_two_rows_per_line() {
+        let mut board = Board::new(2, 4, 1, 1);
+        board.set_cell(0, 0, GameObject::Snake(SnakePart::Tail(Direction::Up)));
+        board.set_cell(0, 1, GameObject::Food);
+        let output = render_ansi(&board);
+
+        let lines: Vec<&str> = output.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].matches(UPPER_HALF_BLOCK).count(), 2);
+        assert!(lines[0].starts_with("\x1b[38;2;0;127;0m\x1b[48;2;255;0;0m▀"));
+        assert!(lines[0].ends_with(RESET));
+    }
+
+    #[test]
+    fn ansi_renderer_only_switches_colors_when_needed() {
+        let board = Board::new(10, 2, 1, 1);
+        let output = render_ansi(&board);
+        assert_eq!(output.matches("\x1b[38;2;").count(), 1);
+    }
+
+    #[test]
+    fn ansi_renderer_handles_odd_heights() {
+        let mut board = Board::new(1, 3, 1, 1);
+        board.set_cell(0, 2, GameObject::Wall);
+        let output = render_ansi(&board);
+        let lines: Vec<&str> = output.split_terminator("\r\n").collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].starts_with("\x1b[38;2;139;69;19m\x1b[49m▀"));
+    }
+}
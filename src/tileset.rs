@@ -0,0 +1,210 @@
+use crate::{Direction, GameObject, SnakePart};
+
+/// Tiles in atlas order, read left to right and top to bottom. Snake tiles are drawn for a snake
+/// moving up: the head faces up, the straight body runs from top to bottom, the corner connects
+/// the top and the right edge and the tail continues towards the top. Wall tiles are picked by
+/// which neighbours are walls too, adding up=1, right=2, down=4 and left=8.
+const FLOOR_TILE: usize = 0;
+const FOOD_TILE: usize = 1;
+const SNAKE_TILES: usize = 2;
+const OPPONENT_TILES: usize = 6;
+const WALL_TILES: usize = 10;
+const TILE_COUNT: usize = 26;
+
+const HEAD: usize = 0;
+const STRAIGHT: usize = 1;
+const CORNER: usize = 2;
+const TAIL: usize = 3;
+
+/// An RGBA sprite atlas of square tiles that cells are drawn with instead of flat colours.
+#[derive(PartialEq)]
+pub struct Tileset {
+    atlas: Vec<u8>,
+    atlas_width: usize,
+    tile_size: usize,
+}
+
+impl Tileset {
+    pub fn new(atlas: Vec<u8>, atlas_width: usize, tile_size: usize) -> Result<Self, String> {
+        if tile_size == 0 || atlas_width == 0 || !atlas_width.is_multiple_of(tile_size) {
+            return Err(format!("Atlas width {atlas_width} is not a multiple of the tile size {tile_size}"));
+        }
+        if !atlas.len().is_multiple_of(atlas_width * 4) {
+            return Err("Atlas size doesn't match its width".to_string());
+        }
+        let atlas_height = atlas.len() / (atlas_width * 4);
+        let tiles = (atlas_width / tile_size) * (atlas_height / tile_size);
+        if tiles < TILE_COUNT {
+            return Err(format!("Atlas holds {tiles} tiles, but {TILE_COUNT} are needed"));
+        }
+        Ok(Tileset { atlas, atlas_width, tile_size })
+    }
+
+    /// Draws `object` into the `cell_width x cell_height` pixels at `(left, top)` of an RGBA
+    /// buffer of `buffer_width x buffer_height` pixels, blending the sprite over the floor tile.
+    /// Parts of the cell outside of the buffer are left out.
+    /// `wall_neighbours` is the wall mask described above and only used for walls.
+    #[allow(clippy::too_many_arguments)]
+    pub fn paint(
+        &self,
+        buffer: &mut [u8],
+        buffer_width: usize,
+        buffer_height: usize,
+        left: usize,
+        top: usize,
+        cell_width: usize,
+        cell_height: usize,
+        object: GameObject,
+        wall_neighbours: usize,
+    ) {
+        let (tile, turns) = tile_for(object, wall_neighbours);
+        for cy in 0..cell_height.min(buffer_height.saturating_sub(top)) {
+            for cx in 0..cell_width.min(buffer_width.saturating_sub(left)) {
+                let u = cx * self.tile_size / cell_width;
+                let v = cy * self.tile_size / cell_height;
+                let floor = self.pixel(FLOOR_TILE, u, v);
+                let (su, sv) = self.rotate(u, v, turns);
+                let sprite = self.pixel(tile, su, sv);
+
+                let alpha = sprite[3] as u32;
+                let index = ((top + cy) * buffer_width + left + cx) * 4;
+                for channel in 0..3 {
+                    let blended = (sprite[channel] as u32 * alpha + floor[channel] as u32 * (255 - alpha)) / 255;
+                    buffer[index + channel] = blended as u8;
+                }
+                buffer[index + 3] = 255;
+            }
+        }
+    }
+
+    /// Maps a pixel of a tile turned clockwise by `turns` quarter turns back to the unturned tile.
+    fn rotate(&self, u: usize, v: usize, turns: usize) -> (usize, usize) {
+        let last = self.tile_size - 1;
+        match turns % 4 {
+            0 => (u, v),
+            1 => (v, last - u),
+            2 => (last - u, last - v),
+            _ => (last - v, u),
+        }
+    }
+
+    fn pixel(&self, tile: usize, u: usize, v: usize) -> [u8; 4] {
+        let tiles_per_row = self.atlas_width / self.tile_size;
+        let x = (tile % tiles_per_row) * self.tile_size + u;
+        let y = (tile / tiles_per_row) * self.tile_size + v;
+        let index = (y * self.atlas_width + x) * 4;
+        [self.atlas[index], self.atlas[index + 1], self.atlas[index + 2], self.atlas[index + 3]]
+    }
+}
+
+/// Picks the tile for a cell and how many clockwise quarter turns it needs.
+fn tile_for(object: GameObject, wall_neighbours: usize) -> (usize, usize) {
+    match object {
+        GameObject::Empty => (FLOOR_TILE, 0),
+        GameObject::Food => (FOOD_TILE, 0),
+        GameObject::Wall => (WALL_TILES + (wall_neighbours & 0xf), 0),
+        GameObject::Snake(part) => snake_tile(SNAKE_TILES, part),
+        GameObject::Opponent(part) => snake_tile(OPPONENT_TILES, part),
+    }
+}
+
+fn snake_tile(first_tile: usize, part: SnakePart) -> (usize, usize) {
+    match part {
+        SnakePart::Head(direction) => (first_tile + HEAD, quarter_turns(direction)),
+        SnakePart::Tail(direction) => (first_tile + TAIL, quarter_turns(direction)),
+        SnakePart::Body(previous, next, _) if previous == next.opposite() => {
+            (first_tile + STRAIGHT, quarter_turns(previous) % 2)
+        }
+        SnakePart::Body(previous, next, _) => {
+            // The corner tile connects up and right, turning it keeps the two edges neighbours
+            let turns = (0..4)
+                .find(|&turns| {
+                    let edges = [turns, (turns + 1) % 4];
+                    edges.contains(&quarter_turns(previous)) && edges.contains(&quarter_turns(next))
+                })
+                .unwrap_or(0);
+            (first_tile + CORNER, turns)
+        }
+    }
+}
+
+fn quarter_turns(direction: Direction) -> usize {
+    match direction {
+        Direction::Right => 1,
+        Direction::Down => 2,
+        Direction::Left => 3,
+        Direction::Up | Direction::Invalid => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An atlas of 2x2 pixel tiles, each filled with its own index in the red channel. The floor
+    /// is opaque, all other tiles only have an opaque green marker in the top left corner.
+    fn numbered_atlas() -> Vec<u8> {
+        let tiles_per_row = 4;
+        let atlas_width = tiles_per_row * 2;
+        let rows = TILE_COUNT.div_ceil(tiles_per_row);
+        let mut atlas = vec![0; atlas_width * rows * 2 * 4];
+        for tile in 0..rows * tiles_per_row {
+            for v in 0..2 {
+                for u in 0..2 {
+                    let x = (tile % tiles_per_row) * 2 + u;
+                    let y = (tile / tiles_per_row) * 2 + v;
+                    let index = (y * atlas_width + x) * 4;
+                    let marker = tile != FLOOR_TILE && u == 0 && v == 0;
+                    atlas[index] = tile as u8;
+                    atlas[index + 1] = if marker { 255 } else { 0 };
+                    atlas[index + 3] = if tile == FLOOR_TILE || marker { 255 } else { 0 };
+                }
+            }
+        }
+        atlas
+    }
+
+    #[test]
+    fn tileset_rejects_invalid_atlases() {
+        assert!(Tileset::new(vec![0; 16], 2, 0).is_err());
+        assert!(Tileset::new(vec![0; 16], 3, 2).is_err());
+        assert!(Tileset::new(vec![0; 15], 2, 2).is_err());
+        assert!(Tileset::new(vec![0; 8 * 2 * 4], 8, 2).is_err());
+        assert!(Tileset::new(numbered_atlas(), 8, 2).is_ok());
+    }
+
+    #[test]
+    fn tileset_orients_snake_parts() {
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Head(Direction::Left)), 0), (2, 3));
+        assert_eq!(tile_for(GameObject::Opponent(SnakePart::Tail(Direction::Down)), 0), (9, 2));
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Body(Direction::Left, Direction::Right, 0)), 0), (3, 1));
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Body(Direction::Up, Direction::Right, 0)), 0), (4, 0));
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Body(Direction::Down, Direction::Right, 0)), 0), (4, 1));
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Body(Direction::Left, Direction::Down, 0)), 0), (4, 2));
+        assert_eq!(tile_for(GameObject::Snake(SnakePart::Body(Direction::Up, Direction::Left, 0)), 0), (4, 3));
+        assert_eq!(tile_for(GameObject::Wall, 5), (15, 0));
+    }
+
+    #[test]
+    fn tileset_blends_rotated_sprites_over_the_floor() {
+        let tileset = Tileset::new(numbered_atlas(), 8, 2).unwrap();
+        let mut buffer = vec![0; 4 * 4 * 4];
+        let head = GameObject::Snake(SnakePart::Head(Direction::Right));
+        tileset.paint(&mut buffer, 4, 4, 2, 2, 2, 2, head, 0);
+
+        let pixel = |x: usize, y: usize| {
+            let index = (y * 4 + x) * 4;
+            [buffer[index], buffer[index + 1], buffer[index + 2], buffer[index + 3]]
+        };
+        // Turning right moves the marker from the top left to the top right corner
+        assert_eq!(pixel(3, 2), [2, 255, 0, 255]);
+        assert_eq!(pixel(2, 2), [0, 0, 0, 255]);
+        assert_eq!(pixel(3, 3), [0, 0, 0, 255]);
+        assert_eq!(pixel(0, 0), [0, 0, 0, 0]);
+
+        // A cell sticking out of the buffer only draws the part inside of it
+        let mut corner = vec![0; 4 * 4 * 4];
+        tileset.paint(&mut corner, 4, 4, 3, 3, 2, 2, head, 0);
+        assert_eq!(corner.iter().filter(|&&byte| byte != 0).count(), 1);
+    }
+}
@@ -0,0 +1,161 @@
+//! Checks claimed scores by playing their replay again, e.g. on a leaderboard server running
+//! the crate natively. The game only depends on its seed and inputs, so an honest replay ends
+//! with exactly the claimed score.
+
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+use crate::highscores::HighScore;
+use crate::replay::Replay;
+use crate::Key;
+
+/// Longer replays are rejected without playing them, one hour of game time.
+pub const MAX_REPLAY_TICKS: u64 = 120 * 60 * 60;
+
+/// A score someone says they reached in a mode, with the replay of the game.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScoreClaim {
+    pub replay: Replay,
+    /// The high-score table the score is claimed for, e.g. "classic" or "greedy".
+    pub mode: String,
+    pub score: u32,
+    pub length: usize,
+}
+
+/// Why a claimed score was not accepted.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Rejection {
+    /// The high-score entry didn't keep its replay.
+    MissingReplay,
+    /// The entry's seed isn't the one its replay was played with.
+    SeedMismatch { claimed: u64, replayed: u64 },
+    UnknownLevel(String),
+    /// The replay was played with other controllers than the claimed mode uses.
+    ModeMismatch { claimed: String, replayed: String },
+    TooLong { ticks: u64 },
+    /// The input at `index` was pressed before the one in front of it.
+    UnsortedInputs { index: usize },
+    /// A key was pressed at or after the tick the replay ends at.
+    InputAfterEnd { tick: u64 },
+    /// Only keys that steer the snake are ever recorded.
+    NotSteering { key: Key },
+    /// The snake died at `tick`, before the end of the replay.
+    EndedEarly { tick: u64 },
+    ScoreMismatch { claimed: u32, replayed: u32 },
+    LengthMismatch { claimed: usize, replayed: usize },
+}
+
+impl Display for Rejection {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::MissingReplay => write!(f, "The entry has no replay"),
+            Rejection::SeedMismatch { claimed, replayed } => {
+                write!(f, "Claimed seed {claimed} but the replay uses {replayed}")
+            }
+            Rejection::UnknownLevel(level) => write!(f, "Unknown level: {level}"),
+            Rejection::ModeMismatch { claimed, replayed } => {
+                write!(f, "Claimed mode {claimed} but the replay plays {replayed}")
+            }
+            Rejection::TooLong { ticks } => write!(f, "Replay of {ticks} ticks is too long to check"),
+            Rejection::UnsortedInputs { index } => write!(f, "Input {index} is older than the one before it"),
+            Rejection::InputAfterEnd { tick } => write!(f, "Input at tick {tick} after the end of the replay"),
+            Rejection::NotSteering { key } => write!(f, "Input {} doesn't steer the snake", key.name()),
+            Rejection::EndedEarly { tick } => write!(f, "The snake died at tick {tick}, before the replay ends"),
+            Rejection::ScoreMismatch { claimed, replayed } => {
+                write!(f, "Claimed score {claimed} but the replay scores {replayed}")
+            }
+            Rejection::LengthMismatch { claimed, replayed } => {
+                write!(f, "Claimed length {claimed} but the replay ends with {replayed}")
+            }
+        }
+    }
+}
+
+impl Error for Rejection {}
+
+impl ScoreClaim {
+    /// The claim a high-score entry of a mode's table makes, if it kept its replay.
+    pub fn from_high_score(mode: &str, entry: &HighScore) -> Result<Self, Rejection> {
+        let replay = entry.replay.clone().ok_or(Rejection::MissingReplay)?;
+        if replay.seed != entry.seed {
+            return Err(Rejection::SeedMismatch { claimed: entry.seed, replayed: replay.seed });
+        }
+        Ok(ScoreClaim { replay, mode: mode.to_string(), score: entry.score, length: entry.length })
+    }
+
+    /// Rejects input logs no game of the claimed mode can have recorded, before spending time on
+    /// playing them.
+    pub fn check_log(&self) -> Result<(), Rejection> {
+        let replay = &self.replay;
+        if replay.mode() != self.mode {
+            return Err(Rejection::ModeMismatch { claimed: self.mode.clone(), replayed: replay.mode() });
+        }
+        if replay.ticks > MAX_REPLAY_TICKS {
+            return Err(Rejection::TooLong { ticks: replay.ticks });
+        }
+        for (index, &(tick, key)) in replay.inputs.iter().enumerate() {
+            if index > 0 && tick < replay.inputs[index - 1].0 {
+                return Err(Rejection::UnsortedInputs { index });
+            }
+            if tick >= replay.ticks {
+                return Err(Rejection::InputAfterEnd { tick });
+            }
+            if !key.steers() {
+                return Err(Rejection::NotSteering { key });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controller::ControllerKind;
+
+    fn claim(inputs: Vec<(u64, Key)>, ticks: u64) -> ScoreClaim {
+        let mut replay = Replay::new("Board 1", 5, Vec::new());
+        replay.inputs = inputs;
+        replay.ticks = ticks;
+        ScoreClaim { replay, mode: "classic".to_string(), score: 0, length: 9 }
+    }
+
+    #[test]
+    fn verify_rejects_impossible_logs() {
+        assert_eq!(claim(vec![(1, Key::ArrowUp), (1, Key::ArrowLeft)], 10).check_log(), Ok(()));
+        assert_eq!(
+            claim(vec![(5, Key::ArrowUp), (3, Key::ArrowLeft)], 10).check_log(),
+            Err(Rejection::UnsortedInputs { index: 1 })
+        );
+        assert_eq!(claim(vec![(10, Key::ArrowUp)], 10).check_log(), Err(Rejection::InputAfterEnd { tick: 10 }));
+        assert_eq!(claim(vec![(2, Key::Space)], 10).check_log(), Err(Rejection::NotSteering { key: Key::Space }));
+        let ticks = MAX_REPLAY_TICKS + 1;
+        assert_eq!(claim(Vec::new(), ticks).check_log(), Err(Rejection::TooLong { ticks }));
+        let mut bot = claim(Vec::new(), 10);
+        bot.replay.player = ControllerKind::Greedy;
+        assert_eq!(
+            bot.check_log(),
+            Err(Rejection::ModeMismatch { claimed: "classic".to_string(), replayed: "greedy".to_string() })
+        );
+    }
+
+    #[test]
+    fn verify_claims_need_the_entry_replay() {
+        let mut entry = HighScore {
+            name: "a".to_string(),
+            score: 10,
+            length: 12,
+            duration_millis: 100,
+            date: 0.0,
+            seed: 5,
+            replay: None,
+        };
+        assert_eq!(ScoreClaim::from_high_score("classic", &entry), Err(Rejection::MissingReplay));
+        entry.replay = Some(Replay::new("Board 1", 6, Vec::new()));
+        assert_eq!(
+            ScoreClaim::from_high_score("classic", &entry),
+            Err(Rejection::SeedMismatch { claimed: 5, replayed: 6 })
+        );
+        entry.seed = 6;
+        assert_eq!(ScoreClaim::from_high_score("classic", &entry).unwrap().score, 10);
+    }
+}
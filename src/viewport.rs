@@ -0,0 +1,212 @@
+use crate::board::Board;
+use crate::canvas::{Canvas, Rect};
+use crate::{Color, GameObject};
+
+/// The camera only moves once the followed cell is further than this part of the visible
+/// columns or rows away from the middle of the view.
+const DEAD_ZONE: usize = 4;
+const MINIMAP_FRAME: Color = Color { r: 255, g: 255, b: 255, a: 255 };
+
+/// The window of board cells that is shown on screen. Boards wrap around, so the window does
+/// too: it can start near the right edge and continue with the leftmost columns.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Viewport {
+    x: usize,
+    y: usize,
+    columns: usize,
+    rows: usize,
+    board_width: usize,
+    board_height: usize,
+}
+
+impl Viewport {
+    /// A window of `columns` x `rows` cells in the top left corner of the board. It never gets
+    /// bigger than the board.
+    pub fn new(board_width: usize, board_height: usize, columns: usize, rows: usize) -> Self {
+        Viewport {
+            x: 0,
+            y: 0,
+            columns: columns.clamp(1, board_width),
+            rows: rows.clamp(1, board_height),
+            board_width,
+            board_height,
+        }
+    }
+
+    /// The board cell in the top left corner of the window.
+    pub fn get_position(&self) -> (usize, usize) {
+        (self.x, self.y)
+    }
+
+    pub fn get_columns(&self) -> usize {
+        self.columns
+    }
+    pub fn get_rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Whether only part of the board fits into the window.
+    pub fn is_scrolling(&self) -> bool {
+        self.columns < self.board_width || self.rows < self.board_height
+    }
+
+    /// The board cell shown at a column and row of the window.
+    pub fn board_cell(&self, column: usize, row: usize) -> (usize, usize) {
+        ((self.x + column) % self.board_width, (self.y + row) % self.board_height)
+    }
+
+    /// The column and row of the window a board cell is shown at, if it is visible.
+    pub fn screen_cell(&self, x: usize, y: usize) -> Option<(usize, usize)> {
+        let column = (x + self.board_width - self.x) % self.board_width;
+        let row = (y + self.board_height - self.y) % self.board_height;
+        (column < self.columns && row < self.rows).then_some((column, row))
+    }
+
+    /// Moves the top left corner of the window to the cell `(x, y)`. Along axes the whole board
+    /// fits into, the window stays at 0.
+    pub fn scroll_to(&mut self, x: usize, y: usize) {
+        self.x = if self.columns < self.board_width { x % self.board_width } else { 0 };
+        self.y = if self.rows < self.board_height { y % self.board_height } else { 0 };
+    }
+
+    /// Moves the window just far enough to keep the cell `(x, y)` inside its dead zone around
+    /// the middle. Returns whether the window moved.
+    pub fn follow(&mut self, x: usize, y: usize) -> bool {
+        let (old_x, old_y) = (self.x, self.y);
+        self.x = follow_axis(self.x, self.columns, self.board_width, x);
+        self.y = follow_axis(self.y, self.rows, self.board_height, y);
+        (self.x, self.y) != (old_x, old_y)
+    }
+}
+
+/// Where a window of `view` cells along an axis of `size` cells has to start to keep `target`
+/// in its dead zone.
+fn follow_axis(start: usize, view: usize, size: usize, target: usize) -> usize {
+    if view >= size {
+        return 0;
+    }
+    // How far the target is from the middle of the window, the shorter way round the board
+    let mut offset = ((target + size - start) % size) as isize - (view / 2) as isize;
+    if offset > size as isize / 2 {
+        offset -= size as isize;
+    } else if offset < -(size as isize / 2) {
+        offset += size as isize;
+    }
+    let margin = (view / DEAD_ZONE) as isize;
+    let shift = if offset > margin {
+        offset - margin
+    } else if offset < -margin {
+        offset + margin
+    } else {
+        0
+    };
+    (start as isize + shift).rem_euclid(size as isize) as usize
+}
+
+/// Draws the whole board shrunk into the bottom right corner with a frame around the part the
+/// viewport shows. Snakes and food win over walls and empty cells when several cells share a
+/// pixel, so they don't disappear on huge boards. Returns the rectangle drawn over.
+pub fn render_minimap(canvas: &mut Canvas, board: &Board) -> Rect {
+    let (board_width, board_height) = (board.get_width(), board.get_height());
+    let size = (canvas.get_width().min(canvas.get_height()) / 5).max(1);
+    let longest_side = board_width.max(board_height);
+    let width = (size * board_width / longest_side).clamp(1, board_width);
+    let height = (size * board_height / longest_side).clamp(1, board_height);
+    let margin = size / 20;
+    let left = canvas.get_width().saturating_sub(width + margin);
+    let top = canvas.get_height().saturating_sub(height + margin);
+
+    let palette = board.get_palette();
+    for map_y in 0..height {
+        let rows = map_y * board_height / height..(map_y + 1) * board_height / height;
+        for map_x in 0..width {
+            let columns = map_x * board_width / width..(map_x + 1) * board_width / width;
+            let object = rows
+                .clone()
+                .flat_map(|y| columns.clone().map(move |x| (x, y)))
+                .filter_map(|(x, y)| board.get_cell(x, y))
+                .max_by_key(|&object| importance(object))
+                .unwrap_or(GameObject::Empty);
+            canvas.fill_rect((left + map_x, top + map_y, 1, 1), palette.color(object));
+        }
+    }
+
+    // The visible window may wrap around, then its frame is drawn in up to four pieces
+    let viewport = board.get_viewport();
+    let (view_x, view_y) = viewport.get_position();
+    let spans = |start: usize, length: usize, size: usize, map_size: usize| {
+        let scale = |cell: usize| cell * map_size / size;
+        let end = start + length;
+        if end <= size {
+            vec![(scale(start), scale(end))]
+        } else {
+            vec![(scale(start), map_size), (0, scale(end - size))]
+        }
+    };
+    for (x_start, x_end) in spans(view_x, viewport.get_columns(), board_width, width) {
+        for &(y_start, y_end) in &spans(view_y, viewport.get_rows(), board_height, height) {
+            let frame = (left + x_start, top + y_start, (x_end - x_start).max(1), (y_end - y_start).max(1));
+            canvas.outline(frame, MINIMAP_FRAME);
+        }
+    }
+    (left, top, width, height)
+}
+
+fn importance(object: GameObject) -> u8 {
+    match object {
+        GameObject::Empty => 0,
+        GameObject::Wall => 1,
+        GameObject::Food => 2,
+        GameObject::Opponent(_) => 3,
+        GameObject::Snake(_) => 4,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn viewport_maps_cells_across_the_board_edge() {
+        let mut viewport = Viewport::new(10, 10, 4, 4);
+        viewport.x = 8;
+        assert_eq!(viewport.board_cell(0, 0), (8, 0));
+        assert_eq!(viewport.board_cell(3, 1), (1, 1));
+        assert_eq!(viewport.screen_cell(1, 1), Some((3, 1)));
+        assert_eq!(viewport.screen_cell(2, 1), None);
+        assert!(viewport.is_scrolling());
+        assert!(!Viewport::new(10, 10, 20, 20).is_scrolling());
+    }
+
+    #[test]
+    fn viewport_follows_outside_the_dead_zone() {
+        let mut viewport = Viewport::new(100, 100, 20, 20);
+        // The middle is at column 10, the dead zone reaches 5 cells from it
+        assert!(!viewport.follow(15, 5));
+        assert!(viewport.follow(16, 10));
+        assert_eq!(viewport.get_position(), (1, 0));
+        // Going left past the edge wraps around instead of stopping at 0
+        assert!(viewport.follow(0, 10));
+        assert_eq!(viewport.get_position(), (95, 0));
+        assert_eq!(viewport.screen_cell(0, 10), Some((5, 10)));
+    }
+
+    #[test]
+    fn viewport_minimap_frames_the_visible_window() {
+        let mut board = Board::new(100, 100, 1, 1);
+        board.set_min_cell_size(4);
+        board.set_screen_size(200, 200);
+        board.set_cell(99, 99, GameObject::Food);
+        let mut buffer = vec![0; 200 * 200 * 4];
+        let mut canvas = Canvas::new(&mut buffer, 200, 200);
+        let rect = render_minimap(&mut canvas, &board);
+        assert_eq!(rect, (158, 158, 40, 40));
+
+        let pixel = |x: usize, y: usize| &buffer[(y * 200 + x) * 4..(y * 200 + x) * 4 + 3];
+        // The food wins over the empty cells around it, the frame covers 50 of 100 columns
+        assert_eq!(pixel(197, 197), &[255, 0, 0]);
+        assert_eq!(pixel(158, 158), &[255, 255, 255]);
+        assert_eq!(pixel(177, 158), &[255, 255, 255]);
+        assert_eq!(pixel(178, 158), &[0, 0, 0]);
+    }
+}